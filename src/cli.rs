@@ -1,6 +1,12 @@
 use clap::Parser;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use crate::config::Config;
 use crate::error::{AppError, Result};
+use crate::hash::fnv1a_64;
+use crate::kicad::{RoundRectPolicy, TextLayout};
+use crate::library::{validate_lib_name, DEFAULT_LIB_NAME};
+use crate::model_path::{ModelFormats, ModelPathMode};
 
 #[derive(Parser, Debug)]
 #[command(name = "nlbn")]
@@ -35,18 +41,74 @@ pub struct Cli {
     #[arg(short, long, default_value = ".")]
     pub output: PathBuf,
 
+    /// Library nickname for symbol, footprint and 3D model files (<NAME>.kicad_sym, <NAME>.pretty, ...)
+    #[arg(long, value_name = "NAME", default_value = DEFAULT_LIB_NAME)]
+    pub lib_name: String,
+
     /// Overwrite existing components
     #[arg(long)]
     pub overwrite: bool,
 
     /// Use KiCad v5 legacy format
-    #[arg(long)]
+    #[arg(long, conflicts_with = "kicad")]
     pub v5: bool,
 
-    /// Use global paths (KICAD6_3DMODEL_DIR) instead of project-relative paths (KIPRJMOD) for 3D models
+    /// Target KiCad version: 5, 6 (also 7), 8 or 9
+    #[arg(long, value_name = "VERSION", conflicts_with = "v5")]
+    pub kicad: Option<KicadVersion>,
+
+    /// Snap symbol pins to a grid (in mils, e.g. 100 or 50)
+    #[arg(long, value_name = "MIL")]
+    pub pin_grid: Option<f64>,
+
+    /// Split slash-delimited pin names (PA9/USART1_TX) into a primary name and alternate functions
+    #[arg(long)]
+    pub split_pin_names: bool,
+
+    /// Store symbols with identical graphics as derived symbols (extends) of a shared base
     #[arg(long)]
+    pub derive_symbols: bool,
+
+    /// Clearance between pads/body and the generated courtyard (in mm)
+    #[arg(long, value_name = "MM", default_value = "0.25")]
+    pub courtyard_clearance: f64,
+
+    /// Round the corners of rectangular SMD pads: none, ipc (IPC-7351) or a fixed ratio (0-0.5)
+    #[arg(long, value_name = "POLICY", default_value = "none")]
+    pub roundrect: RoundRectPolicy,
+
+    /// Clip silkscreen closer than this to pad copper/mask openings (in mm); off when not given
+    #[arg(long, value_name = "MM")]
+    pub silk_clearance: Option<f64>,
+
+    /// Footprint reference/value placement: default or klc (KiCad Library Convention)
+    #[arg(long, value_name = "PROFILE", default_value = "default")]
+    pub text_layout: TextLayout,
+
+    /// 3D model formats to write, comma separated: wrl (colored VRML from OBJ), step, glb (binary glTF) or both
+    #[arg(long = "3d-format", value_name = "FORMAT", default_value = "step")]
+    pub model_formats: ModelFormats,
+
+    /// How footprints reference 3D models: kiprjmod, absolute, env or env:<VAR>
+    #[arg(long, value_name = "MODE", default_value = "kiprjmod")]
+    pub model_path: ModelPathMode,
+
+    /// Deprecated: same as --model-path kiprjmod
+    #[arg(long, hide = true, conflicts_with = "model_path")]
     pub project_relative: bool,
 
+    /// Generate an extruded body (WRL and STEP) for parts without a 3D model
+    #[arg(long)]
+    pub fallback_3d: bool,
+
+    /// Register the output libraries in the sym-lib-table and fp-lib-table of a KiCad project directory
+    #[arg(long, value_name = "DIR", conflicts_with = "register_global")]
+    pub register_project: Option<PathBuf>,
+
+    /// Register the output libraries in the global tables of a KiCad config directory (e.g. ~/.config/kicad/9.0)
+    #[arg(long, value_name = "DIR")]
+    pub register_global: Option<PathBuf>,
+
     /// Enable debug logging
     #[arg(long)]
     pub debug: bool,
@@ -58,26 +120,34 @@ pub struct Cli {
     /// Number of parallel downloads in batch mode (default: 4)
     #[arg(long, default_value = "4")]
     pub parallel: usize,
+
+    /// JSON configuration file (prefix mapping and other conversion settings)
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
 }
 
 impl Cli {
     pub fn validate(&self) -> Result<()> {
-        // Check if at least one ID source is provided
+        // Registering existing libraries needs no components
         if self.lcsc_id.is_none() && self.batch.is_none() {
+            if self.registers_libraries() {
+                return validate_lib_name(&self.lib_name).map_err(AppError::Other);
+            }
             return Err(AppError::Other(
                 "Either --lcsc-id or --batch must be specified".to_string()
             ));
         }
 
         // Validate LCSC ID format if provided
-        if let Some(ref id) = self.lcsc_id {
-            if !id.starts_with('C') || id.len() < 2 {
-                return Err(AppError::Easyeda(
-                    crate::error::EasyedaError::InvalidLcscId(id.clone())
-                ));
-            }
+        if let Some(ref id) = self.lcsc_id
+            && (!id.starts_with('C') || id.len() < 2) {
+            return Err(AppError::Easyeda(
+                crate::error::EasyedaError::InvalidLcscId(id.clone())
+            ));
         }
 
+        validate_lib_name(&self.lib_name).map_err(AppError::Other)?;
+
         // Check if at least one conversion option is selected
         if !self.symbol && !self.footprint && !self.model_3d && !self.full {
             return Err(AppError::Other(
@@ -88,6 +158,11 @@ impl Cli {
         Ok(())
     }
 
+    /// Whether the output libraries should be added to KiCad library tables
+    pub fn registers_libraries(&self) -> bool {
+        self.register_project.is_some() || self.register_global.is_some()
+    }
+
     /// Get list of LCSC IDs to process (either single ID or from batch file)
     pub fn get_lcsc_ids(&self) -> Result<Vec<String>> {
         if let Some(ref id) = self.lcsc_id {
@@ -133,11 +208,53 @@ impl Cli {
         }
     }
 
+    /// Options that affect the generated files, recorded in the lock file
+    pub fn lock_options(&self) -> BTreeMap<String, String> {
+        let mut options = BTreeMap::new();
+        options.insert("kicad".to_string(), format!("{:?}", self.kicad_version()));
+        options.insert("3d_format".to_string(), self.model_formats.to_string());
+        options.insert("model_path".to_string(), self.model_path.to_string());
+        options.insert("courtyard_clearance".to_string(), self.courtyard_clearance.to_string());
+        options.insert("roundrect".to_string(), format!("{:?}", self.roundrect));
+        options.insert("text_layout".to_string(), format!("{:?}", self.text_layout));
+        if let Some(grid) = self.pin_grid {
+            options.insert("pin_grid".to_string(), grid.to_string());
+        }
+        if let Some(clearance) = self.silk_clearance {
+            options.insert("silk_clearance".to_string(), clearance.to_string());
+        }
+        for (name, enabled) in [
+            ("split_pin_names", self.split_pin_names),
+            ("derive_symbols", self.derive_symbols),
+            ("fallback_3d", self.fallback_3d),
+        ] {
+            if enabled {
+                options.insert(name.to_string(), "true".to_string());
+            }
+        }
+        if let Some(config) = &self.config {
+            options.insert("config".to_string(), config.display().to_string());
+            // The path alone does not show edits to the file, so record its content too
+            if let Ok(content) = std::fs::read(config) {
+                options.insert("config_hash".to_string(), format!("{:016x}", fnv1a_64(&content)));
+            }
+        }
+        options
+    }
+
+    /// Load the configuration file, or the defaults if none was given
+    pub fn load_config(&self) -> Result<Config> {
+        match self.config {
+            Some(ref path) => Config::load(path),
+            None => Ok(Config::default()),
+        }
+    }
+
     pub fn kicad_version(&self) -> KicadVersion {
         if self.v5 {
             KicadVersion::V5
         } else {
-            KicadVersion::V6
+            self.kicad.unwrap_or(KicadVersion::V6)
         }
     }
 }
@@ -145,5 +262,24 @@ impl Cli {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum KicadVersion {
     V5,
+    /// KiCad 6 and 7 file formats
     V6,
+    /// KiCad 8: footprint properties and UUIDs
+    V8,
+    /// KiCad 9: KiCad 8 plus embedded fonts
+    V9,
+}
+
+impl std::str::FromStr for KicadVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim_start_matches(['v', 'V']) {
+            "5" => Ok(KicadVersion::V5),
+            "6" | "7" => Ok(KicadVersion::V6),
+            "8" => Ok(KicadVersion::V8),
+            "9" => Ok(KicadVersion::V9),
+            _ => Err(format!("expected KiCad version 5, 6, 7, 8 or 9, got '{}'", s)),
+        }
+    }
 }
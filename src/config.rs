@@ -0,0 +1,290 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use crate::error::{AppError, Result};
+use crate::library::validate_lib_name;
+use crate::kicad::{PadSettings, PasteGrid, PinTextLayout, ZoneConnect};
+
+/// Built-in reference prefix mapping (EasyEDA prefix -> KiCad prefix)
+const DEFAULT_PREFIX_MAP: &[(&str, &str)] = &[
+    ("LED", "D"),
+    ("CN", "J"),
+    ("USB", "J"),
+    ("X", "Y"),
+    ("IC", "U"),
+];
+
+/// Built-in body heights of generated fallback models (package prefix -> mm)
+const DEFAULT_BODY_HEIGHTS: &[(&str, f64)] = &[
+    ("0201", 0.3),
+    ("0402", 0.35),
+    ("0603", 0.45),
+    ("0805", 0.5),
+    ("1206", 0.6),
+    ("1210", 0.6),
+    ("2512", 0.6),
+    ("SOD-123", 1.1),
+    ("SOD-323", 0.9),
+    ("SOT-23", 1.1),
+    ("SOT-223", 1.8),
+    ("SOT-89", 1.5),
+    ("SOIC", 1.75),
+    ("SOP", 1.75),
+    ("MSOP", 1.1),
+    ("TSSOP", 1.2),
+    ("SSOP", 2.0),
+    ("QFN", 0.9),
+    ("DFN", 0.9),
+    ("LQFP", 1.6),
+    ("TQFP", 1.2),
+    ("QFP", 3.0),
+    ("TO-252", 2.3),
+    ("TO-263", 4.5),
+];
+
+/// User configuration loaded from a JSON file (--config)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Reference prefix overrides, e.g. { "LED": "D", "FB": "L" }
+    pub prefix_map: HashMap<String, String>,
+    /// Pin text layout overrides per category (reference prefix), e.g. { "R": { "hide_pin_names": true } }
+    pub pin_layout: HashMap<String, PinLayoutOverride>,
+    /// Footprint-wide pad settings and exposed pad paste windowing
+    pub footprint_defaults: FootprintDefaults,
+    /// Generated 3D bodies for parts without a model (--fallback-3d)
+    pub fallback_body: FallbackBodySetting,
+    /// Library routing rules, checked in order; parts matching none go to --lib-name
+    pub libraries: Vec<LibraryRule>,
+}
+
+/// Sends parts with one of the reference prefixes or LCSC categories to a library
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct LibraryRule {
+    /// Library nickname, e.g. "passives"
+    pub name: String,
+    /// KiCad reference prefixes, e.g. ["R", "C", "L"]
+    pub prefixes: Vec<String>,
+    /// LCSC categories (case-insensitive substring), e.g. ["Connectors"]
+    pub categories: Vec<String>,
+}
+
+/// Heights of generated fallback bodies (in mm)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FallbackBodySetting {
+    /// Height when neither the part nor the package table provides one
+    pub default_height: f64,
+    /// Height per package name prefix, e.g. { "0603": 0.45, "SOIC": 1.75 }
+    pub heights: HashMap<String, f64>,
+}
+
+impl Default for FallbackBodySetting {
+    fn default() -> Self {
+        Self {
+            default_height: 1.0,
+            heights: HashMap::new(),
+        }
+    }
+}
+
+/// Per-category pin text settings; unset fields keep the value derived from EasyEDA
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PinLayoutOverride {
+    pub hide_pin_names: Option<bool>,
+    pub hide_pin_numbers: Option<bool>,
+    pub pin_name_offset: Option<f64>,
+    pub pin_name_size: Option<f64>,
+    pub pin_number_size: Option<f64>,
+}
+
+/// Footprint-wide mask, paste and zone settings (lengths in mm)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FootprintDefaults {
+    pub mask_margin: Option<f64>,
+    pub paste_margin: Option<f64>,
+    pub paste_ratio: Option<f64>,
+    pub zone_connect: Option<ZoneConnect>,
+    pub thermal_width: Option<f64>,
+    pub thermal_gap: Option<f64>,
+    /// Paste aperture grid for exposed pads; off unless configured
+    pub paste_grid: Option<PasteGrid>,
+}
+
+impl FootprintDefaults {
+    pub fn pad_settings(&self) -> PadSettings {
+        PadSettings {
+            mask_margin: self.mask_margin,
+            paste_margin: self.paste_margin,
+            paste_ratio: self.paste_ratio,
+            zone_connect: self.zone_connect,
+            thermal_width: self.thermal_width,
+            thermal_gap: self.thermal_gap,
+        }
+    }
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| AppError::Other(format!("Failed to read config file: {}", e)))?;
+
+        let config: Config = serde_json::from_str(&content)
+            .map_err(|e| AppError::Other(format!("Failed to parse config file: {}", e)))?;
+
+        for rule in &config.libraries {
+            validate_lib_name(&rule.name)
+                .map_err(|e| AppError::Other(format!("Invalid library rule: {}", e)))?;
+        }
+
+        Ok(config)
+    }
+
+    /// Library of the first routing rule matching the reference prefix or a category
+    pub fn library_for(&self, reference: &str, categories: &[String]) -> Option<&str> {
+        self.libraries.iter()
+            .find(|rule| {
+                rule.prefixes.iter().any(|prefix| prefix == reference)
+                    || rule.categories.iter().any(|wanted| {
+                        let wanted = wanted.to_lowercase();
+                        categories.iter().any(|category| category.to_lowercase().contains(&wanted))
+                    })
+            })
+            .map(|rule| rule.name.as_str())
+    }
+
+    /// Map an EasyEDA reference prefix to the KiCad prefix, keeping unknown prefixes as they are
+    pub fn map_prefix(&self, prefix: &str) -> String {
+        if let Some(mapped) = self.prefix_map.get(prefix) {
+            return mapped.clone();
+        }
+
+        if let Some((_, mapped)) = DEFAULT_PREFIX_MAP.iter().find(|(from, _)| *from == prefix) {
+            return mapped.to_string();
+        }
+
+        prefix.to_string()
+    }
+
+    /// Fallback body height of a package: the longest matching configured prefix,
+    /// then the built-in table, then the configured default
+    pub fn body_height(&self, package: &str) -> f64 {
+        let package = package.to_ascii_uppercase();
+        let longest_match = |entries: &mut dyn Iterator<Item = (&str, f64)>| {
+            entries
+                .filter(|(prefix, _)| package.starts_with(&prefix.to_ascii_uppercase()))
+                .max_by_key(|(prefix, _)| prefix.len())
+                .map(|(_, height)| height)
+        };
+
+        longest_match(&mut self.fallback_body.heights.iter().map(|(prefix, height)| (prefix.as_str(), *height)))
+            .or_else(|| longest_match(&mut DEFAULT_BODY_HEIGHTS.iter().copied()))
+            .unwrap_or(self.fallback_body.default_height)
+    }
+
+    /// Apply the pin layout override configured for a reference prefix, if any
+    pub fn apply_pin_layout(&self, reference: &str, layout: &mut PinTextLayout) {
+        let Some(overrides) = self.pin_layout.get(reference) else {
+            return;
+        };
+
+        if let Some(hide) = overrides.hide_pin_names {
+            layout.names_hidden = hide;
+        }
+        if let Some(hide) = overrides.hide_pin_numbers {
+            layout.numbers_hidden = hide;
+        }
+        if let Some(offset) = overrides.pin_name_offset {
+            layout.name_offset = Some(offset);
+        }
+        if let Some(size) = overrides.pin_name_size {
+            layout.name_size = size;
+        }
+        if let Some(size) = overrides.pin_number_size {
+            layout.number_size = size;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_prefix_defaults() {
+        let config = Config::default();
+        assert_eq!(config.map_prefix("R"), "R");
+        assert_eq!(config.map_prefix("LED"), "D");
+    }
+
+    #[test]
+    fn test_map_prefix_override() {
+        let config: Config = serde_json::from_str(r#"{ "prefix_map": { "LED": "LED", "FB": "L" } }"#).unwrap();
+        assert_eq!(config.map_prefix("LED"), "LED");
+        assert_eq!(config.map_prefix("FB"), "L");
+        assert_eq!(config.map_prefix("C"), "C");
+    }
+
+    #[test]
+    fn test_apply_pin_layout() {
+        let config: Config = serde_json::from_str(
+            r#"{ "pin_layout": { "R": { "hide_pin_names": true }, "U": { "pin_name_offset": 1.016 } } }"#
+        ).unwrap();
+
+        let mut layout = PinTextLayout::default();
+        config.apply_pin_layout("R", &mut layout);
+        assert!(layout.names_hidden);
+        assert!(!layout.numbers_hidden);
+
+        let mut layout = PinTextLayout::default();
+        config.apply_pin_layout("U", &mut layout);
+        assert_eq!(layout.name_offset, Some(1.016));
+        assert!(!layout.names_hidden);
+    }
+
+    #[test]
+    fn test_footprint_defaults() {
+        let config: Config = serde_json::from_str(
+            r#"{ "footprint_defaults": { "paste_margin": -0.05, "zone_connect": "solid", "paste_grid": { "rows": 3 } } }"#
+        ).unwrap();
+
+        let settings = config.footprint_defaults.pad_settings();
+        assert_eq!(settings.paste_margin, Some(-0.05));
+        assert_eq!(settings.zone_connect, Some(ZoneConnect::Solid));
+        let grid = config.footprint_defaults.paste_grid.unwrap();
+        assert_eq!((grid.rows, grid.min_size), (3, 2.0));
+
+        let config: Config = serde_json::from_str(r#"{ "footprint_defaults": { "paste_margin": -0.05 } }"#).unwrap();
+        assert!(config.footprint_defaults.paste_grid.is_none());
+    }
+
+    #[test]
+    fn test_library_for() {
+        let config: Config = serde_json::from_str(
+            r#"{ "libraries": [
+                { "name": "passives", "prefixes": ["R", "C", "L"] },
+                { "name": "connectors", "categories": ["connector"] }
+            ] }"#
+        ).unwrap();
+
+        assert_eq!(config.library_for("C", &[]), Some("passives"));
+        assert_eq!(config.library_for("J", &["Connectors".to_string()]), Some("connectors"));
+        assert_eq!(config.library_for("U", &["Microcontrollers".to_string()]), None);
+    }
+
+    #[test]
+    fn test_body_height() {
+        let config: Config = serde_json::from_str(
+            r#"{ "fallback_body": { "default_height": 2.0, "heights": { "SOT-23-5": 1.45 } } }"#
+        ).unwrap();
+
+        assert_eq!(config.body_height("SOT-23-5"), 1.45);
+        assert_eq!(config.body_height("SOT-23-3"), 1.1);
+        assert_eq!(config.body_height("sot-223-3"), 1.8);
+        assert_eq!(config.body_height("HC-49US"), 2.0);
+    }
+}
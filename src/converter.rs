@@ -40,6 +40,26 @@ impl Converter {
         radians * 180.0 / std::f64::consts::PI
     }
 
+    /// Center and radius of the circle through three points, None if they are collinear
+    pub fn arc_center(&self, a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> Option<(f64, f64, f64)> {
+        let (ax, ay) = a;
+        let (bx, by) = b;
+        let (cx, cy) = c;
+
+        let d = 2.0 * (ax * (by - cy) + bx * (cy - ay) + cx * (ay - by));
+        if d.abs() < 1e-9 {
+            return None;
+        }
+
+        let a2 = ax * ax + ay * ay;
+        let b2 = bx * bx + by * by;
+        let c2 = cx * cx + cy * cy;
+        let ux = (a2 * (by - cy) + b2 * (cy - ay) + c2 * (ay - by)) / d;
+        let uy = (a2 * (cx - bx) + b2 * (ax - cx) + c2 * (bx - ax)) / d;
+
+        Some((ux, uy, ((ax - ux).powi(2) + (ay - uy).powi(2)).sqrt()))
+    }
+
     /// Compute arc center from SVG elliptical arc endpoint parameters
     /// Based on W3C SVG specification for arc conversion
     /// Returns (center_x, center_y, start_angle_deg, end_angle_deg)
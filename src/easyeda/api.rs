@@ -33,7 +33,15 @@ impl EasyedaApi {
             return Err(EasyedaError::ComponentNotFound(lcsc_id.to_string()).into());
         }
 
-        let api_response: ApiResponse = response.json()
+        let body = response.bytes()
+            .map_err(EasyedaError::ApiRequest)?;
+
+        Self::parse_component_data(lcsc_id, &body)
+    }
+
+    /// Build the component from the body of a components API response
+    fn parse_component_data(lcsc_id: &str, body: &[u8]) -> Result<ComponentData> {
+        let api_response: ApiResponse = serde_json::from_slice(body)
             .map_err(|e| EasyedaError::InvalidData(format!("Failed to parse JSON: {}", e)))?;
 
         if !api_response.success {
@@ -99,8 +107,43 @@ impl EasyedaApi {
             .unwrap_or("")
             .to_string();
 
-        log::debug!("Extracted metadata: manufacturer={}, datasheet={}, jlc_id={}",
-                   manufacturer, datasheet, jlc_id);
+        // Reference designator prefix, e.g. "R?" or "U?"
+        let prefix = data_str_obj.get("head")
+            .and_then(|h| h.get("c_para"))
+            .and_then(|cp| cp.get("pre"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+
+        // Package name, e.g. "0603" or "SOT-23-3"
+        let package = data_str_obj.get("head")
+            .and_then(|h| h.get("c_para"))
+            .and_then(|cp| cp.get("package"))
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+
+        // Body height for generated fallback models, e.g. "1.1mm"
+        let body_height = data_str_obj.get("head")
+            .and_then(|h| h.get("c_para"))
+            .and_then(|cp| cp.get("Height").or_else(|| cp.get("height")))
+            .and_then(Self::parse_length_mm);
+
+        // Category tags of the part, used for library routing
+        let categories: Vec<String> = result.tags.as_ref()
+            .and_then(|tags| tags.as_array())
+            .map(|tags| tags.iter().filter_map(|tag| tag.as_str().map(|s| s.to_string())).collect())
+            .unwrap_or_default();
+
+        // Upstream revision, recorded in the lock file
+        let revision = match &result.update_time {
+            Some(serde_json::Value::String(time)) => time.clone(),
+            Some(serde_json::Value::Number(time)) => time.to_string(),
+            _ => String::new(),
+        };
+
+        log::debug!("Extracted metadata: manufacturer={}, datasheet={}, jlc_id={}, prefix={}, package={}",
+                   manufacturer, datasheet, jlc_id, prefix, package);
 
         // Parse package_detail - it's an object with a "dataStr" that has a "shape" array
         let (package_detail, package_bbox_x, package_bbox_y, model_3d) = if let Some(pkg) = result.package_detail {
@@ -158,6 +201,12 @@ impl EasyedaApi {
             manufacturer,
             datasheet,
             jlc_id,
+            prefix,
+            package,
+            body_height,
+            categories,
+            revision,
+            raw_response: body.to_vec(),
         })
     }
 
@@ -168,22 +217,38 @@ impl EasyedaApi {
                 let parts: Vec<&str> = shape.split('~').collect();
                 if parts.len() > 1 {
                     // Parse the JSON in the second part
-                    if let Ok(svg_data) = serde_json::from_str::<serde_json::Value>(parts[1]) {
-                        if let Some(attrs) = svg_data.get("attrs") {
-                            // Check if this is a 3D model (c_etype == "outline3D")
-                            if let Some(c_etype) = attrs.get("c_etype").and_then(|v| v.as_str()) {
-                                if c_etype == "outline3D" {
-                                    let uuid = attrs.get("uuid")
-                                        .and_then(|v| v.as_str())
-                                        .map(|s| s.to_string());
-                                    let title = attrs.get("title")
-                                        .and_then(|v| v.as_str())
-                                        .map(|s| s.to_string());
-
-                                    if let (Some(uuid), Some(title)) = (uuid, title) {
-                                        return Some(Model3dInfo { uuid, title });
-                                    }
-                                }
+                    if let Ok(svg_data) = serde_json::from_str::<serde_json::Value>(parts[1])
+                        && let Some(attrs) = svg_data.get("attrs") {
+                        // Check if this is a 3D model (c_etype == "outline3D")
+                        if let Some(c_etype) = attrs.get("c_etype").and_then(|v| v.as_str())
+                            && c_etype == "outline3D" {
+                            let uuid = attrs.get("uuid")
+                                .and_then(|v| v.as_str())
+                                .map(|s| s.to_string());
+                            let title = attrs.get("title")
+                                .and_then(|v| v.as_str())
+                                .map(|s| s.to_string());
+
+                            if let (Some(uuid), Some(title)) = (uuid, title) {
+                                // Placement: "x,y" origin, z offset and "rx,ry,rz" rotation
+                                let origin = Self::parse_number_list(attrs.get("c_origin"));
+                                let rotation = Self::parse_number_list(attrs.get("c_rotation"));
+                                let z = Self::parse_number_list(attrs.get("z"));
+
+                                return Some(Model3dInfo {
+                                    uuid,
+                                    title,
+                                    origin: (
+                                        origin.first().copied().unwrap_or(0.0),
+                                        origin.get(1).copied().unwrap_or(0.0),
+                                    ),
+                                    z: z.first().copied().unwrap_or(0.0),
+                                    rotation: (
+                                        rotation.first().copied().unwrap_or(0.0),
+                                        rotation.get(1).copied().unwrap_or(0.0),
+                                        rotation.get(2).copied().unwrap_or(0.0),
+                                    ),
+                                });
                             }
                         }
                     }
@@ -193,6 +258,30 @@ impl EasyedaApi {
         None
     }
 
+    /// Comma separated numbers of an SVGNODE attribute (given as string or number)
+    fn parse_number_list(value: Option<&serde_json::Value>) -> Vec<f64> {
+        match value {
+            Some(serde_json::Value::Number(n)) => n.as_f64().into_iter().collect(),
+            Some(serde_json::Value::String(s)) => s.split(',')
+                .filter_map(|part| part.trim().parse::<f64>().ok())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Length in mm given as a number or a string such as "1.1mm"
+    fn parse_length_mm(value: &serde_json::Value) -> Option<f64> {
+        let height = match value {
+            serde_json::Value::Number(n) => n.as_f64()?,
+            serde_json::Value::String(s) => {
+                let s = s.trim();
+                s.strip_suffix("mm").unwrap_or(s).trim().parse::<f64>().ok()?
+            }
+            _ => return None,
+        };
+        (height > 0.0).then_some(height)
+    }
+
     pub fn download_3d_obj(&self, uuid: &str) -> Result<Vec<u8>> {
         let url = format!("https://modules.easyeda.com/3dmodel/{}", uuid);
 
@@ -239,3 +328,44 @@ impl Default for EasyedaApi {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_3d_model_placement() {
+        let attrs = r#"{"attrs":{"c_etype":"outline3D","uuid":"abc","title":"SOT-23","c_origin":"4001.5,2998.25","z":"-1.2","c_rotation":"0,0,90"}}"#;
+        let shapes = vec!["TRACK~1~3~~0 0 1 1".to_string(), format!("SVGNODE~{}", attrs)];
+
+        let model = EasyedaApi::extract_3d_model_from_svgnode(&shapes).unwrap();
+        assert_eq!(model.title, "SOT-23");
+        assert_eq!(model.origin, (4001.5, 2998.25));
+        assert_eq!(model.z, -1.2);
+        assert_eq!(model.rotation, (0.0, 0.0, 90.0));
+    }
+
+    #[test]
+    fn test_parse_component_tags_and_prefix() {
+        let body = br#"{
+            "success": true,
+            "result": {
+                "title": "RC0603FR-0710KL",
+                "dataStr": {
+                    "head": { "x": 400, "y": 300, "c_para": { "pre": "R?", "package": "0603", "Height": "0.45mm" } },
+                    "shape": []
+                },
+                "tags": ["Resistors", "Chip Resistor - Surface Mount"],
+                "updateTime": 1700000000
+            }
+        }"#;
+
+        let data = EasyedaApi::parse_component_data("C25804", body).unwrap();
+        assert_eq!(data.categories, vec!["Resistors", "Chip Resistor - Surface Mount"]);
+        assert_eq!(data.prefix, "R?");
+        assert_eq!(data.package, "0603");
+        assert_eq!(data.body_height, Some(0.45));
+        assert_eq!(data.revision, "1700000000");
+        assert_eq!(data.raw_response, body);
+    }
+}
@@ -4,12 +4,12 @@ use crate::easyeda::models::*;
 pub struct SymbolImporter;
 
 impl SymbolImporter {
-    pub fn parse(data_str: &[String]) -> Result<EeSymbol> {
+    pub fn parse(data_str: &[String], prefix: &str) -> Result<EeSymbol> {
         log::debug!("Parsing symbol with {} shapes", data_str.len());
 
         let mut symbol = EeSymbol {
             name: String::new(),
-            prefix: String::new(),
+            prefix: Self::reference_prefix(prefix),
             pins: Vec::new(),
             rectangles: Vec::new(),
             circles: Vec::new(),
@@ -95,7 +95,8 @@ impl SymbolImporter {
                     }
                 }
                 "T" => {
-                    // Text: T~x~y~rotation~text~id~locked~layerid~type~fontSize
+                    // Text: T~mark~x~y~rotation~color~font~fontSize~...~text~visible
+                    // (older data: T~x~y~rotation~text~id~locked~layerid~type~fontSize)
                     if let Ok(text) = Self::parse_text(&fields) {
                         symbol.texts.push(text);
                     }
@@ -106,27 +107,33 @@ impl SymbolImporter {
                         symbol.paths.push(path);
                     }
                 }
-                "LIB" => {
+                "LIB" if fields.len() > 3 => {
                     // Library info: LIB~x~y~package~id~locked
-                    if fields.len() > 3 {
-                        symbol.name = fields[3].to_string();
-                    }
+                    symbol.name = fields[3].to_string();
                 }
                 _ => {}
             }
         }
 
-        // Set default prefix if not found
-        if symbol.prefix.is_empty() {
-            symbol.prefix = "U".to_string();
-        }
-
         log::info!("Parsed symbol: {} pins, {} rectangles, {} circles, {} ellipses, {} polylines",
                    symbol.pins.len(), symbol.rectangles.len(), symbol.circles.len(), symbol.ellipses.len(), symbol.polylines.len());
 
         Ok(symbol)
     }
 
+    /// Strip the designator placeholder from an EasyEDA prefix ("R?" -> "R", "U1" -> "U")
+    pub fn parse_prefix(raw: &str) -> String {
+        raw.trim()
+            .trim_end_matches(|c: char| c == '?' || c.is_ascii_digit())
+            .to_string()
+    }
+
+    /// Reference prefix of a part, "U" when EasyEDA has none
+    pub fn reference_prefix(raw: &str) -> String {
+        let prefix = Self::parse_prefix(raw);
+        if prefix.is_empty() { "U".to_string() } else { prefix }
+    }
+
     fn parse_pin(pin_data: &str) -> Result<EePin> {
         // Pin data contains multiple segments separated by ^^
         // Segment 0: P~is_displayed~type~spice_pin_number~pos_x~pos_y~rotation~id~is_locked
@@ -171,6 +178,10 @@ impl SymbolImporter {
             "PIN".to_string()
         };
 
+        // Name (segment 3) and number (segment 4) visibility flags
+        let name_visible = Self::segment_displayed(&segments, 3);
+        let number_visible = Self::segment_displayed(&segments, 4);
+
         // Extract pin length from segment 2 (path) if available
         let length = if segments.len() > 2 {
             let path_fields: Vec<&str> = segments[2].split('~').collect();
@@ -211,14 +222,25 @@ impl SymbolImporter {
             y,
             rotation,
             length,
-            name_visible: true,
-            number_visible: true,
+            name_visible,
+            number_visible,
             electric_type,
             dot: false,
             clock: false,
         })
     }
 
+    /// Read the is_displayed flag of a pin segment ("1"/"show" = visible, missing = visible)
+    fn segment_displayed(segments: &[&str], index: usize) -> bool {
+        match segments.get(index) {
+            Some(segment) => {
+                let flag = segment.split('~').next().unwrap_or("");
+                flag == "1" || flag == "show"
+            }
+            None => true,
+        }
+    }
+
     fn parse_rectangle(fields: &[&str]) -> Result<EeRectangle> {
         if fields.len() < 7 {
             return Err(EasyedaError::InvalidData("Invalid rectangle data".to_string()).into());
@@ -386,6 +408,32 @@ impl SymbolImporter {
             return Err(EasyedaError::InvalidData("Invalid text data".to_string()).into());
         }
 
+        // Current format starts with a text mark:
+        // T~mark~x~y~rotation~color~font~fontSize~weight~style~baseline~type~text~visible~anchor~id
+        if fields[1].parse::<f64>().is_err() {
+            if fields.len() < 13 {
+                return Err(EasyedaError::InvalidData("Invalid text data".to_string()).into());
+            }
+            if fields.get(13) == Some(&"0") {
+                return Err(EasyedaError::InvalidData("Hidden text".to_string()).into());
+            }
+
+            let x = fields[2].parse::<f64>()
+                .map_err(|_| EasyedaError::InvalidData("Invalid text X".to_string()))?;
+            let y = fields[3].parse::<f64>()
+                .map_err(|_| EasyedaError::InvalidData("Invalid text Y".to_string()))?;
+            let rotation = fields[4].parse::<i32>().unwrap_or(0);
+            let font_size = fields[7].trim_end_matches("pt").parse::<f64>().unwrap_or(7.0);
+
+            return Ok(EeText {
+                text: fields[12].to_string(),
+                x,
+                y,
+                rotation,
+                font_size,
+            });
+        }
+
         let x = fields[1].parse::<f64>()
             .map_err(|_| EasyedaError::InvalidData("Invalid text X".to_string()))?;
         let y = fields[2].parse::<f64>()
@@ -766,3 +814,43 @@ impl FootprintImporter {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_prefix() {
+        assert_eq!(SymbolImporter::parse_prefix("R?"), "R");
+        assert_eq!(SymbolImporter::parse_prefix("C?"), "C");
+        assert_eq!(SymbolImporter::parse_prefix(" J? "), "J");
+        assert_eq!(SymbolImporter::parse_prefix("U12"), "U");
+        assert_eq!(SymbolImporter::parse_prefix("LED?"), "LED");
+        assert_eq!(SymbolImporter::parse_prefix(""), "");
+        assert_eq!(SymbolImporter::parse_prefix("?"), "");
+    }
+
+    #[test]
+    fn test_empty_prefix_defaults_to_u() {
+        let symbol = SymbolImporter::parse(&[], "").unwrap();
+        assert_eq!(symbol.prefix, "U");
+
+        let symbol = SymbolImporter::parse(&[], "D?").unwrap();
+        assert_eq!(symbol.prefix, "D");
+        assert_eq!(SymbolImporter::reference_prefix(" ? "), "U");
+    }
+
+    #[test]
+    fn test_parse_text() {
+        let fields: Vec<&str> = "T~L~402~305~270~#0000FF~~7pt~~~~comment~+5V~1~start~gge12~0".split('~').collect();
+        let text = SymbolImporter::parse_text(&fields).unwrap();
+        assert_eq!((text.text.as_str(), text.x, text.y, text.rotation, text.font_size), ("+5V", 402.0, 305.0, 270, 7.0));
+
+        let hidden: Vec<&str> = "T~L~402~305~0~#0000FF~~7pt~~~~comment~NC~0~start~gge13~0".split('~').collect();
+        assert!(SymbolImporter::parse_text(&hidden).is_err());
+
+        let legacy: Vec<&str> = "T~400~300~90~GND~gge14~0~1~comment~8".split('~').collect();
+        let text = SymbolImporter::parse_text(&legacy).unwrap();
+        assert_eq!((text.text.as_str(), text.x, text.y, text.rotation), ("GND", 400.0, 300.0, 90));
+    }
+}
@@ -14,12 +14,33 @@ pub struct ComponentData {
     pub manufacturer: String,
     pub datasheet: String,
     pub jlc_id: String,
+    #[serde(default)]
+    pub prefix: String,  // Raw designator prefix from c_para (e.g. "R?")
+    #[serde(default)]
+    pub package: String,  // Package name from c_para (e.g. "0603")
+    #[serde(default)]
+    pub body_height: Option<f64>,  // Body height in mm from c_para, if given
+    #[serde(default)]
+    pub categories: Vec<String>,  // LCSC category tags (e.g. "Connectors")
+    #[serde(default)]
+    pub revision: String,  // EasyEDA updateTime of the part
+    #[serde(skip)]
+    pub raw_response: Vec<u8>,  // API response body as received, hashed into the lock file
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Model3dInfo {
     pub uuid: String,
     pub title: String,
+    /// Model origin on the EasyEDA canvas (c_origin, in px)
+    #[serde(default)]
+    pub origin: (f64, f64),
+    /// Height offset of the model (z, in px)
+    #[serde(default)]
+    pub z: f64,
+    /// Rotation around x, y and z (c_rotation, in degrees, clockwise)
+    #[serde(default)]
+    pub rotation: (f64, f64, f64),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,6 +57,9 @@ pub struct ApiResult {
     #[serde(rename = "packageDetail")]
     pub package_detail: Option<serde_json::Value>,
     pub lcsc: Option<serde_json::Value>,
+    pub tags: Option<serde_json::Value>,
+    #[serde(rename = "updateTime")]
+    pub update_time: Option<serde_json::Value>,
 }
 
 // EasyEDA Symbol structures
@@ -29,6 +29,9 @@ pub enum KicadError {
     #[error("Failed to export 3D model: {0}")]
     ModelExport(String),
 
+    #[error("Failed to update library table: {0}")]
+    LibTable(String),
+
     #[error("Invalid KiCad version")]
     InvalidVersion,
 
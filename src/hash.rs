@@ -0,0 +1,43 @@
+/// Stable content hashing (FNV-1a), used where hashes must not change between runs
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// 64-bit FNV-1a hash of a byte slice
+pub fn fnv1a_64(data: &[u8]) -> u64 {
+    data.iter().fold(FNV_OFFSET_BASIS, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Deterministic UUID (version 8, RFC 9562 custom layout) derived from a key,
+/// so regenerated files keep the same identifiers
+pub fn stable_uuid(key: &str) -> String {
+    let high = fnv1a_64(key.as_bytes());
+    let low = fnv1a_64(format!("{}#", key).as_bytes());
+    let bytes = ((high as u128) << 64 | low as u128)
+        & !(0xf000_u128 << 64) & !(0xc000_u128 << 48)
+        | (0x8000_u128 << 64) | (0x8000_u128 << 48);
+    let hex = format!("{:032x}", bytes);
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv1a_64() {
+        assert_eq!(fnv1a_64(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a_64(b"a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn test_stable_uuid() {
+        let uuid = stable_uuid("C2040/3");
+        assert_eq!(uuid, stable_uuid("C2040/3"));
+        assert_ne!(uuid, stable_uuid("C2040/4"));
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "8");
+        assert!(matches!(&uuid[19..20], "8" | "9" | "a" | "b"));
+    }
+}
@@ -0,0 +1,445 @@
+use crate::kicad::footprint::{KiFootprint, PX_PER_MM};
+use std::f64::consts::PI;
+
+/// VRML units are 0.1 inch
+const MM_PER_WRL_UNIT: f64 = 2.54;
+
+/// Body color of generated models (dark gray plastic)
+const BODY_COLOR: (f64, f64, f64) = (0.2, 0.2, 0.2);
+
+/// Segments of the polygon used for round bodies
+const CIRCLE_SEGMENTS: usize = 32;
+
+/// Outline endpoints closer than this are joined (in mm)
+const JOIN_TOLERANCE_MM: f64 = 0.001;
+
+/// Simple prism standing on the board, used when a part has no 3D model
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtrudedBody {
+    /// Counter-clockwise outline in KiCad 3D coordinates (mm, y up)
+    pub outline: Vec<(f64, f64)>,
+    /// Extrusion height above the board (in mm)
+    pub height: f64,
+}
+
+impl ExtrudedBody {
+    /// Body following the closed fab outline (polygon or circle). Without fab
+    /// graphics the courtyard outline is used, shrunk by `courtyard_clearance` mm.
+    pub fn from_footprint(footprint: &KiFootprint, height: f64, courtyard_clearance: f64) -> Option<Self> {
+        if height <= 0.0 {
+            return None;
+        }
+
+        let outline = layer_outline(footprint, "F.Fab")
+            .or_else(|| layer_outline(footprint, "F.CrtYd").and_then(|outline| offset(&outline, -courtyard_clearance)))?;
+        Some(Self::new(outline, height))
+    }
+
+    /// Extrude a closed outline; the winding is normalized to counter-clockwise
+    pub fn new(mut outline: Vec<(f64, f64)>, height: f64) -> Self {
+        if signed_area(&outline) < 0.0 {
+            outline.reverse();
+        }
+        Self { outline, height }
+    }
+
+    /// Bottom ring followed by the top ring
+    fn vertices(&self) -> Vec<(f64, f64, f64)> {
+        let bottom = self.outline.iter().map(|&(x, y)| (x, y, 0.0));
+        let top = self.outline.iter().map(|&(x, y)| (x, y, self.height));
+        bottom.chain(top).collect()
+    }
+
+    /// Vertex indices of each face, wound so that normals point outwards
+    fn faces(&self) -> Vec<Vec<usize>> {
+        let n = self.outline.len();
+        let mut faces = vec![(0..n).rev().collect::<Vec<_>>(), (n..2 * n).collect()];
+        for i in 0..n {
+            let j = (i + 1) % n;
+            faces.push(vec![i, j, n + j, n + i]);
+        }
+        faces
+    }
+
+    /// VRML 2.0 model with a single shape
+    pub fn to_wrl(&self) -> String {
+        let mut output = String::new();
+        output.push_str("#VRML V2.0 utf8\n");
+        output.push_str("#Generated by nlbn (fallback body)\n\n");
+        output.push_str("Shape {\n");
+        output.push_str("  appearance Appearance {\n");
+        output.push_str("    material Material {\n");
+        output.push_str(&format!(
+            "      diffuseColor {:.2} {:.2} {:.2}\n",
+            BODY_COLOR.0, BODY_COLOR.1, BODY_COLOR.2
+        ));
+        output.push_str("    }\n");
+        output.push_str("  }\n");
+        output.push_str("  geometry IndexedFaceSet {\n");
+        output.push_str("    coord Coordinate {\n");
+        output.push_str("      point [\n");
+        let points: Vec<String> = self.vertices().iter()
+            .map(|v| format!(
+                "        {:.6} {:.6} {:.6}",
+                v.0 / MM_PER_WRL_UNIT, v.1 / MM_PER_WRL_UNIT, v.2 / MM_PER_WRL_UNIT
+            ))
+            .collect();
+        output.push_str(&points.join(",\n"));
+        output.push_str("\n      ]\n");
+        output.push_str("    }\n");
+        output.push_str("    coordIndex [\n");
+        let faces: Vec<String> = self.faces().iter()
+            .map(|face| {
+                let indices: Vec<String> = face.iter().map(|idx| idx.to_string()).collect();
+                format!("      {}, -1", indices.join(", "))
+            })
+            .collect();
+        output.push_str(&faces.join(",\n"));
+        output.push_str("\n    ]\n");
+        output.push_str("    solid TRUE\n");
+        output.push_str("  }\n");
+        output.push_str("}\n");
+        output
+    }
+
+    /// AP214 STEP file with the body as a faceted B-rep (in mm)
+    pub fn to_step(&self, name: &str) -> String {
+        let name = name.replace('\'', "''");
+        let mut data = StepData::default();
+
+        let context = data.add("APPLICATION_CONTEXT('automotive design')".to_string());
+        data.add(format!("APPLICATION_PROTOCOL_DEFINITION('international standard','automotive_design',2000,#{})", context));
+        let product_context = data.add(format!("PRODUCT_CONTEXT('',#{},'mechanical')", context));
+        let product = data.add(format!("PRODUCT('{0}','{0}','',(#{1}))", name, product_context));
+        let definition_context = data.add(format!("PRODUCT_DEFINITION_CONTEXT('part definition',#{},'design')", context));
+        let formation = data.add(format!("PRODUCT_DEFINITION_FORMATION('','',#{})", product));
+        let definition = data.add(format!("PRODUCT_DEFINITION('design','',#{},#{})", formation, definition_context));
+        let shape = data.add(format!("PRODUCT_DEFINITION_SHAPE('','',#{})", definition));
+
+        let length = data.add("(LENGTH_UNIT()NAMED_UNIT(*)SI_UNIT(.MILLI.,.METRE.))".to_string());
+        let angle = data.add("(NAMED_UNIT(*)PLANE_ANGLE_UNIT()SI_UNIT($,.RADIAN.))".to_string());
+        let solid_angle = data.add("(NAMED_UNIT(*)SI_UNIT($,.STERADIAN.)SOLID_ANGLE_UNIT())".to_string());
+        let uncertainty = data.add(format!(
+            "UNCERTAINTY_MEASURE_WITH_UNIT(LENGTH_MEASURE(1.E-07),#{},'distance_accuracy_value','confusion accuracy')",
+            length
+        ));
+        let geometry_context = data.add(format!(
+            "(GEOMETRIC_REPRESENTATION_CONTEXT(3)GLOBAL_UNCERTAINTY_ASSIGNED_CONTEXT((#{}))\
+             GLOBAL_UNIT_ASSIGNED_CONTEXT((#{},#{},#{}))REPRESENTATION_CONTEXT('',''))",
+            uncertainty, length, angle, solid_angle
+        ));
+
+        let origin = data.add("CARTESIAN_POINT('',(0.,0.,0.))".to_string());
+        let axis = data.add("DIRECTION('',(0.,0.,1.))".to_string());
+        let ref_direction = data.add("DIRECTION('',(1.,0.,0.))".to_string());
+        let placement = data.add(format!("AXIS2_PLACEMENT_3D('',#{},#{},#{})", origin, axis, ref_direction));
+
+        let points: Vec<usize> = self.vertices().iter()
+            .map(|v| data.add(format!(
+                "CARTESIAN_POINT('',({},{},{}))",
+                step_real(v.0), step_real(v.1), step_real(v.2)
+            )))
+            .collect();
+
+        // Faceted B-rep faces are planar FACE_SURFACEs bounded by a POLY_LOOP
+        let vertices = self.vertices();
+        let faces: Vec<usize> = self.faces().iter()
+            .map(|face| {
+                let refs: Vec<String> = face.iter().map(|&idx| format!("#{}", points[idx])).collect();
+                let poly_loop = data.add(format!("POLY_LOOP('',({}))", refs.join(",")));
+                let bound = data.add(format!("FACE_OUTER_BOUND('',#{},.T.)", poly_loop));
+
+                let (normal, ref_direction) = face_frame(&vertices, face);
+                let normal = data.add(format!(
+                    "DIRECTION('',({},{},{}))",
+                    step_real(normal.0), step_real(normal.1), step_real(normal.2)
+                ));
+                let ref_direction = data.add(format!(
+                    "DIRECTION('',({},{},{}))",
+                    step_real(ref_direction.0), step_real(ref_direction.1), step_real(ref_direction.2)
+                ));
+                let frame = data.add(format!("AXIS2_PLACEMENT_3D('',#{},#{},#{})", points[face[0]], normal, ref_direction));
+                let plane = data.add(format!("PLANE('',#{})", frame));
+                data.add(format!("FACE_SURFACE('',(#{}),#{},.T.)", bound, plane))
+            })
+            .collect();
+        let face_refs: Vec<String> = faces.iter().map(|id| format!("#{}", id)).collect();
+
+        let shell = data.add(format!("CLOSED_SHELL('',({}))", face_refs.join(",")));
+        let brep = data.add(format!("FACETED_BREP('{}',#{})", name, shell));
+        let representation = data.add(format!(
+            "FACETED_BREP_SHAPE_REPRESENTATION('',(#{},#{}),#{})",
+            brep, placement, geometry_context
+        ));
+        data.add(format!("SHAPE_DEFINITION_REPRESENTATION(#{},#{})", shape, representation));
+
+        let mut output = String::new();
+        output.push_str("ISO-10303-21;\n");
+        output.push_str("HEADER;\n");
+        output.push_str("FILE_DESCRIPTION(('nlbn fallback body'),'2;1');\n");
+        output.push_str(&format!("FILE_NAME('{}.step','',(''),(''),'nlbn','nlbn','');\n", name));
+        output.push_str("FILE_SCHEMA(('AUTOMOTIVE_DESIGN { 1 0 10303 214 1 1 1 1 }'));\n");
+        output.push_str("ENDSEC;\n");
+        output.push_str("DATA;\n");
+        for (idx, entity) in data.entities.iter().enumerate() {
+            output.push_str(&format!("#{}={};\n", idx + 1, entity));
+        }
+        output.push_str("ENDSEC;\n");
+        output.push_str("END-ISO-10303-21;\n");
+        output
+    }
+}
+
+/// Unit normal (Newell's method, following the winding) and in-plane reference
+/// direction (along the first edge) of a planar face
+fn face_frame(vertices: &[(f64, f64, f64)], face: &[usize]) -> ((f64, f64, f64), (f64, f64, f64)) {
+    let mut normal = (0.0, 0.0, 0.0);
+    for (i, &idx) in face.iter().enumerate() {
+        let a = vertices[idx];
+        let b = vertices[face[(i + 1) % face.len()]];
+        normal.0 += (a.1 - b.1) * (a.2 + b.2);
+        normal.1 += (a.2 - b.2) * (a.0 + b.0);
+        normal.2 += (a.0 - b.0) * (a.1 + b.1);
+    }
+
+    let (a, b) = (vertices[face[0]], vertices[face[1]]);
+    let edge = (b.0 - a.0, b.1 - a.1, b.2 - a.2);
+    (normalize(normal), normalize(edge))
+}
+
+fn normalize(v: (f64, f64, f64)) -> (f64, f64, f64) {
+    let length = (v.0 * v.0 + v.1 * v.1 + v.2 * v.2).sqrt();
+    if length == 0.0 {
+        return v;
+    }
+    (v.0 / length, v.1 / length, v.2 / length)
+}
+
+/// Largest closed outline on a layer, in KiCad 3D coordinates (mm, y up).
+///
+/// Lines and arcs are joined end to end into loops; circles become polygons.
+fn layer_outline(footprint: &KiFootprint, layer: &str) -> Option<Vec<(f64, f64)>> {
+    let to_mm = |x: f64, y: f64| (x / PX_PER_MM, -y / PX_PER_MM);
+
+    let mut segments: Vec<((f64, f64), (f64, f64))> = footprint.lines.iter()
+        .filter(|line| line.layer == layer)
+        .map(|line| (to_mm(line.start_x, line.start_y), to_mm(line.end_x, line.end_y)))
+        .collect();
+    for arc in footprint.arcs.iter().filter(|arc| arc.layer == layer) {
+        let mid = to_mm(arc.mid_x, arc.mid_y);
+        segments.push((to_mm(arc.start_x, arc.start_y), mid));
+        segments.push((mid, to_mm(arc.end_x, arc.end_y)));
+    }
+
+    let mut outlines = closed_loops(segments);
+    for circle in footprint.circles.iter().filter(|circle| circle.layer == layer) {
+        let radius = (circle.end_x - circle.center_x).hypot(circle.end_y - circle.center_y) / PX_PER_MM;
+        let center = to_mm(circle.center_x, circle.center_y);
+        outlines.push((0..CIRCLE_SEGMENTS)
+            .map(|step| {
+                let theta = 2.0 * PI * step as f64 / CIRCLE_SEGMENTS as f64;
+                (center.0 + radius * theta.cos(), center.1 + radius * theta.sin())
+            })
+            .collect());
+    }
+
+    outlines.into_iter()
+        .filter(|outline| signed_area(outline).abs() > 0.0)
+        .max_by(|a, b| signed_area(a).abs().total_cmp(&signed_area(b).abs()))
+}
+
+/// Join segments end to end; returns every chain that closes on itself
+fn closed_loops(mut segments: Vec<((f64, f64), (f64, f64))>) -> Vec<Vec<(f64, f64)>> {
+    let near = |a: (f64, f64), b: (f64, f64)| (a.0 - b.0).hypot(a.1 - b.1) < JOIN_TOLERANCE_MM;
+    let mut loops = Vec::new();
+
+    while let Some((start, mut end)) = segments.pop() {
+        let mut points = vec![start];
+        while !near(end, start) {
+            let Some(idx) = segments.iter().position(|&(a, b)| near(a, end) || near(b, end)) else {
+                break;
+            };
+            let (a, b) = segments.swap_remove(idx);
+            points.push(end);
+            end = if near(a, end) { b } else { a };
+        }
+        if near(end, start) && points.len() >= 3 {
+            loops.push(points);
+        }
+    }
+
+    loops
+}
+
+/// Move every edge of a simple polygon outwards by `distance` (inwards when negative)
+fn offset(outline: &[(f64, f64)], distance: f64) -> Option<Vec<(f64, f64)>> {
+    let n = outline.len();
+    // Outward normals point right of the edges of a counter-clockwise outline
+    let sign = if signed_area(outline) > 0.0 { 1.0 } else { -1.0 };
+    let shifted: Vec<((f64, f64), (f64, f64))> = (0..n)
+        .map(|i| {
+            let (a, b) = (outline[i], outline[(i + 1) % n]);
+            let length = (b.0 - a.0).hypot(b.1 - a.1);
+            let normal = ((b.1 - a.1) / length * sign * distance, -(b.0 - a.0) / length * sign * distance);
+            ((a.0 + normal.0, a.1 + normal.1), (b.0 + normal.0, b.1 + normal.1))
+        })
+        .collect();
+
+    // New corners where neighbouring shifted edges meet
+    let result: Vec<(f64, f64)> = (0..n)
+        .map(|i| {
+            let (p, q) = (shifted[(i + n - 1) % n], shifted[i]);
+            let r = (p.1.0 - p.0.0, p.1.1 - p.0.1);
+            let s = (q.1.0 - q.0.0, q.1.1 - q.0.1);
+            let denom = r.0 * s.1 - r.1 * s.0;
+            if denom.abs() < 1e-12 {
+                // Collinear edges: the shared corner just moves along the normal
+                return q.0;
+            }
+            let t = ((q.0.0 - p.0.0) * s.1 - (q.0.1 - p.0.1) * s.0) / denom;
+            (p.0.0 + r.0 * t, p.0.1 + r.1 * t)
+        })
+        .collect();
+
+    // Shrinking past the middle flips the outline
+    (signed_area(&result) * signed_area(outline) > 0.0).then_some(result)
+}
+
+/// Shoelace area, positive for counter-clockwise outlines
+fn signed_area(outline: &[(f64, f64)]) -> f64 {
+    outline.iter()
+        .zip(outline.iter().cycle().skip(1))
+        .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+        .sum::<f64>() / 2.0
+}
+
+/// Numbered entity instances of a STEP data section
+#[derive(Default)]
+struct StepData {
+    entities: Vec<String>,
+}
+
+impl StepData {
+    fn add(&mut self, entity: String) -> usize {
+        self.entities.push(entity);
+        self.entities.len()
+    }
+}
+
+/// STEP reals always carry a decimal point
+fn step_real(value: f64) -> String {
+    let value = if value.abs() < 1e-9 { 0.0 } else { value };
+    format!("{:.6}", value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kicad::footprint::{KiCircle, KiLine};
+    use crate::kicad::step::inspect_step;
+
+    #[test]
+    fn test_outline_is_counter_clockwise() {
+        let body = ExtrudedBody::new(vec![(0.0, 0.0), (0.0, 2.0), (3.0, 2.0), (3.0, 0.0)], 1.0);
+        assert_eq!(body.outline[..2], [(3.0, 0.0), (3.0, 2.0)]);
+
+        // Bottom and top caps plus one side per outline edge
+        let wrl = body.to_wrl();
+        assert_eq!(wrl.matches("-1").count(), 6);
+        assert!(wrl.contains("1.181102 0.000000 0.000000"));
+    }
+
+    #[test]
+    fn test_step_output_is_valid() {
+        let body = ExtrudedBody::new(vec![(-1.6, -0.8), (1.6, -0.8), (1.6, 0.8), (-1.6, 0.8)], 0.55);
+        let info = inspect_step(body.to_step("1206").as_bytes()).unwrap();
+
+        assert_eq!(info.product_name.as_deref(), Some("1206"));
+        assert_eq!(info.unit, "mm");
+        let size = info.size();
+        assert!((size.0 - 3.2).abs() < 1e-9 && (size.1 - 1.6).abs() < 1e-9 && (size.2 - 0.55).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_step_faces_lie_on_planes() {
+        let body = ExtrudedBody::new(vec![(-1.6, -0.8), (1.6, -0.8), (1.6, 0.8), (-1.6, 0.8)], 0.55);
+        let step = body.to_step("1206");
+
+        assert_eq!(step.matches("=FACE_SURFACE('',(").count(), 6);
+        assert_eq!(step.matches("=PLANE('',").count(), 6);
+        assert!(!step.contains("=FACE('"));
+
+        // Bottom faces down, top faces up, sides face outwards
+        let vertices = body.vertices();
+        let normals: Vec<(f64, f64, f64)> = body.faces().iter().map(|face| face_frame(&vertices, face).0).collect();
+        assert_eq!(normals[0], (0.0, 0.0, -1.0));
+        assert_eq!(normals[1], (0.0, 0.0, 1.0));
+        assert_eq!(normals[2], (0.0, -1.0, 0.0));
+        assert_eq!(normals[3], (1.0, 0.0, 0.0));
+    }
+
+    fn outline(points: &[(f64, f64)], layer: &str) -> Vec<KiLine> {
+        (0..points.len())
+            .map(|i| {
+                let (a, b) = (points[i], points[(i + 1) % points.len()]);
+                KiLine {
+                    start_x: a.0 * PX_PER_MM,
+                    start_y: a.1 * PX_PER_MM,
+                    end_x: b.0 * PX_PER_MM,
+                    end_y: b.1 * PX_PER_MM,
+                    width: 0.1 * PX_PER_MM,
+                    layer: layer.to_string(),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_body_follows_fab_polygon() {
+        // Chamfered fab outline with the lines out of order, plus a silk line
+        let mut lines = outline(&[(-0.5, -0.5), (1.0, -0.5), (1.0, 0.5), (-1.0, 0.5), (-1.0, 0.0)], "F.Fab");
+        lines.swap(0, 3);
+        lines.push(outline(&[(-2.0, 0.0), (2.0, 0.0)], "F.SilkS")[0].clone());
+        let fp = KiFootprint { lines, ..KiFootprint::default() };
+
+        let body = ExtrudedBody::from_footprint(&fp, 1.0, 0.25).unwrap();
+        assert_eq!(body.outline.len(), 5);
+        assert!((signed_area(&body.outline) - 1.875).abs() < 1e-9);
+        assert!(body.outline.contains(&(1.0, 0.5)));
+    }
+
+    #[test]
+    fn test_round_body_from_fab_circle() {
+        let fp = KiFootprint {
+            circles: vec![KiCircle {
+                center_x: 0.0,
+                center_y: 0.0,
+                end_x: 2.5 * PX_PER_MM,
+                end_y: 0.0,
+                width: 0.1 * PX_PER_MM,
+                layer: "F.Fab".to_string(),
+                fill: false,
+            }],
+            ..KiFootprint::default()
+        };
+
+        let body = ExtrudedBody::from_footprint(&fp, 11.0, 0.25).unwrap();
+        assert_eq!(body.outline.len(), CIRCLE_SEGMENTS);
+        assert!(body.outline.iter().all(|p| (p.0.hypot(p.1) - 2.5).abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_courtyard_is_shrunk_by_clearance() {
+        let lines = outline(&[(-1.25, -0.75), (1.25, -0.75), (1.25, 0.75), (-1.25, 0.75)], "F.CrtYd");
+        let fp = KiFootprint { lines, ..KiFootprint::default() };
+
+        let body = ExtrudedBody::from_footprint(&fp, 0.5, 0.25).unwrap();
+        let xs: Vec<f64> = body.outline.iter().map(|p| p.0.abs()).collect();
+        let ys: Vec<f64> = body.outline.iter().map(|p| p.1.abs()).collect();
+        assert!(xs.iter().all(|x| (x - 1.0).abs() < 1e-9) && ys.iter().all(|y| (y - 0.5).abs() < 1e-9));
+
+        // Nothing left after the clearance, or nothing closed to extrude
+        assert!(ExtrudedBody::from_footprint(&fp, 0.5, 1.0).is_none());
+        assert!(ExtrudedBody::from_footprint(&KiFootprint::default(), 0.5, 0.25).is_none());
+    }
+}
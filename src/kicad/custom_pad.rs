@@ -0,0 +1,279 @@
+use crate::kicad::footprint::{CustomPad, CustomPadAnchor, KiPad, PadShape};
+
+/// Tolerance (in EasyEDA px) for geometric comparisons
+const EPSILON: f64 = 1e-6;
+
+/// Keep the anchor pad slightly inside the polygon so it never defines the outline
+const ANCHOR_MARGIN: f64 = 0.95;
+
+/// A rect anchor is used when it is (almost) as large as the largest circle
+const RECT_ANCHOR_RATIO: f64 = 0.99;
+
+/// Grid resolution used to search for an interior pad origin
+const ORIGIN_SAMPLES: usize = 16;
+
+/// Placement of a custom pad derived from an EasyEDA polygon (all values in px)
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomPadLayout {
+    /// Pad origin on the board; may differ from the EasyEDA pad position when
+    /// that point lies outside the polygon
+    pub position: (f64, f64),
+    /// Side length (rect) or diameter (circle) of the anchor pad
+    pub anchor_size: f64,
+    pub custom: CustomPad,
+}
+
+/// Build a custom pad from a polygon outline in board coordinates.
+///
+/// The outline is stored relative to the pad origin and un-rotated, so KiCad
+/// applies the pad rotation to it. The anchor is sized to fit inside the polygon.
+/// With `fixed_origin` (through-hole pads, where the drill sits at the origin)
+/// the pad position is kept; otherwise a better interior point may be chosen.
+/// Returns None when no point of the polygon can hold an anchor.
+pub fn build_custom_pad(
+    position: (f64, f64),
+    rotation: f64,
+    outline: &[(f64, f64)],
+    fixed_origin: bool,
+) -> Option<CustomPadLayout> {
+    let mut outline = outline.to_vec();
+    if outline.len() > 1 && outline.first() == outline.last() {
+        outline.pop();
+    }
+    if outline.len() < 3 {
+        return None;
+    }
+
+    let origin = if fixed_origin {
+        position
+    } else {
+        choose_origin(position, &outline)?
+    };
+
+    let local: Vec<(f64, f64)> = outline.iter()
+        .map(|&(x, y)| to_local((x - origin.0, y - origin.1), rotation))
+        .collect();
+
+    let clearance = inner_clearance((0.0, 0.0), &local)?;
+    let half_square = largest_square(&local, clearance);
+
+    let (anchor, anchor_size) = if half_square >= clearance * RECT_ANCHOR_RATIO {
+        (CustomPadAnchor::Rect, 2.0 * half_square * ANCHOR_MARGIN)
+    } else {
+        (CustomPadAnchor::Circle, 2.0 * clearance * ANCHOR_MARGIN)
+    };
+
+    Some(CustomPadLayout {
+        position: origin,
+        anchor_size,
+        custom: CustomPad { anchor, outline: local },
+    })
+}
+
+/// Check that the anchor of a custom pad lies completely inside its polygon
+pub fn anchor_fits(pad: &KiPad) -> bool {
+    let Some(custom) = &pad.custom else {
+        return true;
+    };
+    if pad.shape != PadShape::Custom {
+        return false;
+    }
+
+    let half_x = pad.size_x / 2.0;
+    let half_y = pad.size_y / 2.0;
+    match custom.anchor {
+        CustomPadAnchor::Circle => inner_clearance((0.0, 0.0), &custom.outline)
+            .is_some_and(|clearance| half_x.max(half_y) <= clearance + EPSILON),
+        CustomPadAnchor::Rect => rect_inside(&custom.outline, half_x, half_y),
+    }
+}
+
+/// Convert an offset on the board into the pad frame (inverse of the pad rotation)
+fn to_local((dx, dy): (f64, f64), rotation: f64) -> (f64, f64) {
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    (dx * cos - dy * sin, dx * sin + dy * cos)
+}
+
+/// Prefer the EasyEDA pad position; otherwise use the interior point (centroid,
+/// bounding box center or a grid sample) farthest from the outline
+fn choose_origin(position: (f64, f64), outline: &[(f64, f64)]) -> Option<(f64, f64)> {
+    let min_x = outline.iter().map(|p| p.0).fold(f64::MAX, f64::min);
+    let min_y = outline.iter().map(|p| p.1).fold(f64::MAX, f64::min);
+    let max_x = outline.iter().map(|p| p.0).fold(f64::MIN, f64::max);
+    let max_y = outline.iter().map(|p| p.1).fold(f64::MIN, f64::max);
+
+    let mut candidates = vec![centroid(outline), ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0)];
+    for i in 1..ORIGIN_SAMPLES {
+        for j in 1..ORIGIN_SAMPLES {
+            let fx = i as f64 / ORIGIN_SAMPLES as f64;
+            let fy = j as f64 / ORIGIN_SAMPLES as f64;
+            candidates.push((min_x + fx * (max_x - min_x), min_y + fy * (max_y - min_y)));
+        }
+    }
+
+    let (best, best_clearance) = candidates.into_iter()
+        .filter_map(|point| inner_clearance(point, outline).map(|clearance| (point, clearance)))
+        .fold(None, |best: Option<((f64, f64), f64)>, candidate| match best {
+            Some(current) if current.1 >= candidate.1 => Some(current),
+            _ => Some(candidate),
+        })?;
+
+    match inner_clearance(position, outline) {
+        Some(clearance) if clearance >= best_clearance * 0.5 => Some(position),
+        _ => Some(best),
+    }
+}
+
+/// Area centroid of a polygon (vertex average for degenerate polygons)
+fn centroid(outline: &[(f64, f64)]) -> (f64, f64) {
+    let mut area = 0.0;
+    let (mut cx, mut cy) = (0.0, 0.0);
+    for (idx, &(x0, y0)) in outline.iter().enumerate() {
+        let (x1, y1) = outline[(idx + 1) % outline.len()];
+        let cross = x0 * y1 - x1 * y0;
+        area += cross;
+        cx += (x0 + x1) * cross;
+        cy += (y0 + y1) * cross;
+    }
+
+    if area.abs() < EPSILON {
+        let n = outline.len() as f64;
+        return (
+            outline.iter().map(|p| p.0).sum::<f64>() / n,
+            outline.iter().map(|p| p.1).sum::<f64>() / n,
+        );
+    }
+    (cx / (3.0 * area), cy / (3.0 * area))
+}
+
+/// Distance from an interior point to the polygon outline, None if outside
+fn inner_clearance(point: (f64, f64), outline: &[(f64, f64)]) -> Option<f64> {
+    if !contains(outline, point) {
+        return None;
+    }
+
+    let distance = (0..outline.len())
+        .map(|idx| segment_distance(point, outline[idx], outline[(idx + 1) % outline.len()]))
+        .fold(f64::MAX, f64::min);
+    Some(distance)
+}
+
+/// Half side of the largest origin-centered square inside the polygon
+fn largest_square(outline: &[(f64, f64)], clearance: f64) -> f64 {
+    // The inscribed square of the clearance circle always fits
+    let (mut low, mut high) = (clearance / 2f64.sqrt(), clearance);
+    if rect_inside(outline, high, high) {
+        return high;
+    }
+    for _ in 0..32 {
+        let mid = (low + high) / 2.0;
+        if rect_inside(outline, mid, mid) {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
+/// Whether the origin-centered rectangle lies inside the polygon
+fn rect_inside(outline: &[(f64, f64)], half_x: f64, half_y: f64) -> bool {
+    let corners = [(-half_x, -half_y), (half_x, -half_y), (half_x, half_y), (-half_x, half_y)];
+    if !corners.iter().all(|&corner| contains(outline, corner) || on_outline(outline, corner)) {
+        return false;
+    }
+
+    // No polygon vertex may poke into the rectangle and no edge may cross it
+    let vertex_inside = outline.iter()
+        .any(|p| p.0.abs() < half_x - EPSILON && p.1.abs() < half_y - EPSILON);
+    let edge_crosses = (0..corners.len()).any(|side| {
+        let (a, b) = (corners[side], corners[(side + 1) % corners.len()]);
+        (0..outline.len()).any(|idx| segments_cross(a, b, outline[idx], outline[(idx + 1) % outline.len()]))
+    });
+
+    !vertex_inside && !edge_crosses
+}
+
+/// Even-odd point in polygon test
+fn contains(outline: &[(f64, f64)], (x, y): (f64, f64)) -> bool {
+    let mut inside = false;
+    for (idx, &(x0, y0)) in outline.iter().enumerate() {
+        let (x1, y1) = outline[(idx + 1) % outline.len()];
+        if (y0 > y) != (y1 > y) && x < x0 + (y - y0) * (x1 - x0) / (y1 - y0) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+fn on_outline(outline: &[(f64, f64)], point: (f64, f64)) -> bool {
+    (0..outline.len())
+        .any(|idx| segment_distance(point, outline[idx], outline[(idx + 1) % outline.len()]) < EPSILON)
+}
+
+fn segment_distance(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq < EPSILON {
+        0.0
+    } else {
+        (((p.0 - a.0) * dx + (p.1 - a.1) * dy) / length_sq).clamp(0.0, 1.0)
+    };
+    (p.0 - (a.0 + t * dx)).hypot(p.1 - (a.1 + t * dy))
+}
+
+/// Proper crossing of two segments (touching endpoints do not count)
+fn segments_cross(a: (f64, f64), b: (f64, f64), c: (f64, f64), d: (f64, f64)) -> bool {
+    let orient = |p: (f64, f64), q: (f64, f64), r: (f64, f64)| {
+        (q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0)
+    };
+    let (d1, d2) = (orient(a, b, c), orient(a, b, d));
+    let (d3, d4) = (orient(c, d, a), orient(c, d, b));
+    d1 * d2 < -EPSILON && d3 * d4 < -EPSILON
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rect_anchor_for_rectangular_outline() {
+        let outline = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+        let layout = build_custom_pad((5.0, 5.0), 0.0, &outline, false).unwrap();
+
+        assert_eq!(layout.position, (5.0, 5.0));
+        assert_eq!(layout.custom.anchor, CustomPadAnchor::Rect);
+        assert!((layout.anchor_size - 9.5).abs() < 1e-6);
+        assert_eq!(layout.custom.outline[0], (-5.0, -5.0));
+    }
+
+    #[test]
+    fn test_origin_moved_inside_and_anchor_fits() {
+        // L-shaped pad whose EasyEDA position lies in the missing quadrant
+        let outline = [(0.0, 0.0), (10.0, 0.0), (10.0, 4.0), (4.0, 4.0), (4.0, 10.0), (0.0, 10.0)];
+        let layout = build_custom_pad((8.0, 8.0), 90.0, &outline, false).unwrap();
+        assert_ne!(layout.position, (8.0, 8.0));
+        assert!(layout.anchor_size > 3.0);
+
+        let pad = KiPad {
+            number: "1".to_string(),
+            pad_type: crate::kicad::footprint::PadType::Smd,
+            shape: PadShape::Custom,
+            pos_x: layout.position.0,
+            pos_y: layout.position.1,
+            size_x: layout.anchor_size,
+            size_y: layout.anchor_size,
+            rotation: 90.0,
+            layers: Vec::new(),
+            drill: None,
+            custom: Some(layout.custom),
+            roundrect_ratio: 0.0,
+            chamfer: None,
+            settings: crate::kicad::footprint::PadSettings::default(),
+        };
+        assert!(anchor_fits(&pad));
+
+        let oversized = KiPad { size_x: 20.0, size_y: 20.0, ..pad };
+        assert!(!anchor_fits(&oversized));
+    }
+}
@@ -1,6 +1,16 @@
-#[derive(Debug, Clone)]
+use serde::Deserialize;
+
+/// EasyEDA px per millimeter (1 px = 10 mil = 0.254 mm)
+pub(crate) const PX_PER_MM: f64 = 1.0 / 0.254;
+
+#[derive(Debug, Clone, Default)]
 pub struct KiFootprint {
     pub name: String,
+    pub lcsc_id: String,
+    pub datasheet: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub properties: Vec<(String, String)>,  // Extra fields, e.g. ("LCSC", "C2040")
     pub pads: Vec<KiPad>,
     pub tracks: Vec<KiTrack>,
     pub circles: Vec<KiCircle>,
@@ -8,10 +18,87 @@ pub struct KiFootprint {
     pub texts: Vec<KiText>,
     pub lines: Vec<KiLine>,
     pub model_3d: Option<Ki3dModel>,
+    pub settings: PadSettings,  // Footprint-wide defaults for all pads
+}
+
+impl KiFootprint {
+    /// Placement attribute derived from the pads: any plated hole makes the part
+    /// through-hole, otherwise copper pads make it SMD
+    pub fn attribute(&self) -> Option<FootprintAttribute> {
+        if self.pads.iter().any(|pad| pad.pad_type == PadType::ThroughHole) {
+            Some(FootprintAttribute::ThroughHole)
+        } else if self.pads.iter().any(|pad| matches!(pad.pad_type, PadType::Smd | PadType::Connect)) {
+            Some(FootprintAttribute::Smd)
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FootprintAttribute {
+    Smd,
+    ThroughHole,
+}
+
+impl FootprintAttribute {
+    pub fn to_kicad(&self) -> &'static str {
+        match self {
+            FootprintAttribute::Smd => "smd",
+            FootprintAttribute::ThroughHole => "through_hole",
+        }
+    }
+}
+
+/// Axis-aligned bounding box (in EasyEDA px, like the footprint coordinates)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BBox {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+impl BBox {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { min_x: x, min_y: y, max_x: x, max_y: y }
+    }
+
+    /// Box centered on (x, y) with the given half extents
+    pub fn around(x: f64, y: f64, half_x: f64, half_y: f64) -> Self {
+        Self { min_x: x - half_x, min_y: y - half_y, max_x: x + half_x, max_y: y + half_y }
+    }
+
+    pub fn union(&self, other: &BBox) -> BBox {
+        BBox {
+            min_x: self.min_x.min(other.min_x),
+            min_y: self.min_y.min(other.min_y),
+            max_x: self.max_x.max(other.max_x),
+            max_y: self.max_y.max(other.max_y),
+        }
+    }
+
+    pub fn expand(&self, margin: f64) -> BBox {
+        BBox {
+            min_x: self.min_x - margin,
+            min_y: self.min_y - margin,
+            max_x: self.max_x + margin,
+            max_y: self.max_y + margin,
+        }
+    }
+
+    pub fn width(&self) -> f64 {
+        self.max_x - self.min_x
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max_y - self.min_y
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum PadType {
+    #[default]
     Smd,
     ThroughHole,
     NpThroughHole,
@@ -29,9 +116,10 @@ impl PadType {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum PadShape {
     Circle,
+    #[default]
     Rect,
     Oval,
     Trapezoid,
@@ -62,7 +150,7 @@ impl PadShape {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct KiPad {
     pub number: String,
     pub pad_type: PadType,
@@ -74,7 +162,115 @@ pub struct KiPad {
     pub rotation: f64,
     pub layers: Vec<String>,
     pub drill: Option<Drill>,
-    pub polygon: Option<String>,  // For custom polygon pads
+    pub custom: Option<CustomPad>,  // Outline of custom (polygon) pads
+    pub roundrect_ratio: f64,  // Corner radius / smaller pad side, for roundrect pads
+    pub chamfer: Option<PadChamfer>,
+    pub settings: PadSettings,
+}
+
+/// Mask, paste and zone settings of a pad or footprint (lengths in mm).
+/// Unset values inherit from the footprint, then from the board.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PadSettings {
+    pub mask_margin: Option<f64>,
+    pub paste_margin: Option<f64>,
+    pub paste_ratio: Option<f64>,  // Relative paste margin, e.g. -0.1 shrinks paste by 10%
+    pub zone_connect: Option<ZoneConnect>,
+    pub thermal_width: Option<f64>,
+    pub thermal_gap: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ZoneConnect {
+    None,
+    Thermal,
+    Solid,
+}
+
+impl ZoneConnect {
+    pub fn to_kicad(&self) -> i32 {
+        match self {
+            ZoneConnect::None => 0,
+            ZoneConnect::Thermal => 1,
+            ZoneConnect::Solid => 2,
+        }
+    }
+}
+
+/// Shape of the anchor pad underneath a custom pad's polygon
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CustomPadAnchor {
+    Circle,
+    Rect,
+}
+
+impl CustomPadAnchor {
+    pub fn to_kicad(&self) -> &'static str {
+        match self {
+            CustomPadAnchor::Circle => "circle",
+            CustomPadAnchor::Rect => "rect",
+        }
+    }
+}
+
+/// Custom pad polygon, relative to the pad origin and before pad rotation (in px)
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomPad {
+    pub anchor: CustomPadAnchor,
+    pub outline: Vec<(f64, f64)>,
+}
+
+/// Chamfered corners of a roundrect pad
+#[derive(Debug, Clone, PartialEq)]
+pub struct PadChamfer {
+    pub ratio: f64,  // Chamfer size / smaller pad side
+    pub corners: Vec<PadCorner>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PadCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl PadCorner {
+    pub fn to_kicad(&self) -> &'static str {
+        match self {
+            PadCorner::TopLeft => "top_left",
+            PadCorner::TopRight => "top_right",
+            PadCorner::BottomLeft => "bottom_left",
+            PadCorner::BottomRight => "bottom_right",
+        }
+    }
+}
+
+impl KiPad {
+    /// Copper extents of the pad, taking its rotation into account
+    pub fn bbox(&self) -> BBox {
+        let half_x = self.size_x / 2.0;
+        let half_y = self.size_y / 2.0;
+
+        if self.shape == PadShape::Circle {
+            return BBox::around(self.pos_x, self.pos_y, half_x, half_x);
+        }
+
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+
+        // Custom pads: rotate the outline into board coordinates
+        if let Some(custom) = &self.custom {
+            let anchor = BBox::around(self.pos_x, self.pos_y, half_x.max(half_y), half_x.max(half_y));
+            return custom.outline.iter()
+                .map(|&(x, y)| BBox::new(self.pos_x + x * cos + y * sin, self.pos_y - x * sin + y * cos))
+                .fold(anchor, |bbox, point| bbox.union(&point));
+        }
+
+        let extent_x = (half_x * cos).abs() + (half_y * sin).abs();
+        let extent_y = (half_x * sin).abs() + (half_y * cos).abs();
+        BBox::around(self.pos_x, self.pos_y, extent_x, extent_y)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -1,58 +1,210 @@
 use crate::converter::Converter;
 use crate::error::Result;
 use crate::kicad::footprint::*;
+use crate::kicad::footprint_outline::{text_placement, TextLayout, TEXT_SIZE_MM};
 use crate::cli::KicadVersion;
+use crate::hash::{fnv1a_64, stable_uuid};
+
+/// Generator version recorded in KiCad 8+ footprint headers
+const GENERATOR_VERSION: &str = "0.3";
 
 pub struct FootprintExporter {
+    version: KicadVersion,
     converter: Converter,
+    text_layout: TextLayout,
 }
 
 impl FootprintExporter {
-    pub fn new() -> Self {
+    pub fn new(version: KicadVersion) -> Self {
         Self {
-            converter: Converter::new(KicadVersion::V6),
+            version,
+            converter: Converter::new(version),
+            text_layout: TextLayout::Default,
         }
     }
 
+    /// Set how the reference and value texts are placed
+    pub fn with_text_layout(mut self, text_layout: TextLayout) -> Self {
+        self.text_layout = text_layout;
+        self
+    }
+
     pub fn export(&self, footprint: &KiFootprint) -> Result<String> {
         let mut output = String::new();
 
+        // KiCad 8 rewrites missing UUIDs on every save, so derive them from the part
+        let uuid_seed = if footprint.lcsc_id.is_empty() { &footprint.name } else { &footprint.lcsc_id };
+        let mut item_index = 0;
+        let mut uuid = || {
+            item_index += 1;
+            self.uuid(uuid_seed, item_index)
+        };
+
         // Module header
-        output.push_str(&format!("(footprint \"{}\" (version 20221018) (generator nlbn)\n", footprint.name));
-        output.push_str("  (layer \"F.Cu\")\n");
+        match self.version {
+            KicadVersion::V5 => {
+                // KiCad 5 module; tedit is derived from the name so output stays stable between runs
+                output.push_str(&format!(
+                    "(module \"{}\" (layer F.Cu) (tedit {:08X})\n",
+                    footprint.name,
+                    fnv1a_64(footprint.name.as_bytes()) as u32
+                ));
+            }
+            KicadVersion::V6 => {
+                output.push_str(&format!("(footprint \"{}\" (version 20221018) (generator nlbn)\n", footprint.name));
+                output.push_str("  (layer \"F.Cu\")\n");
+            }
+            KicadVersion::V8 | KicadVersion::V9 => {
+                let file_version = if self.version == KicadVersion::V9 { 20241229 } else { 20240108 };
+                output.push_str(&format!(
+                    "(footprint \"{}\" (version {}) (generator \"nlbn\") (generator_version \"{}\")\n",
+                    footprint.name,
+                    file_version,
+                    GENERATOR_VERSION
+                ));
+                output.push_str("  (layer \"F.Cu\")\n");
+            }
+        }
 
-        // Reference and value text
-        output.push_str("  (fp_text reference \"REF**\" (at 0 0) (layer \"F.SilkS\")\n");
-        output.push_str("    (effects (font (size 1 1) (thickness 0.15)))\n");
-        output.push_str("  )\n");
+        // Description and search keywords for the footprint browser
+        if !footprint.description.is_empty() {
+            output.push_str(&format!("  (descr \"{}\")\n", escape(&footprint.description)));
+        }
+        if !footprint.tags.is_empty() {
+            output.push_str(&format!("  (tags \"{}\")\n", escape(&footprint.tags.join(" "))));
+        }
+
+        // Reference above and value below the footprint, reference variable on the fab body
+        let placement = text_placement(footprint, self.text_layout);
+        let reference_at = (self.converter.px_to_mm(placement.reference.0), self.converter.px_to_mm(placement.reference.1));
+        let value_at = (self.converter.px_to_mm(placement.value.0), self.converter.px_to_mm(placement.value.1));
+
+        // KiCad 8 stores reference, value and fields as footprint properties
+        let properties = matches!(self.version, KicadVersion::V8 | KicadVersion::V9);
+        if properties {
+            output.push_str(&self.format_property("Reference", "REF**", reference_at, "F.SilkS", false, &uuid()));
+            output.push_str(&self.format_property("Value", &footprint.name, value_at, "F.Fab", false, &uuid()));
+            output.push_str(&self.format_property("Datasheet", &footprint.datasheet, (0.0, 0.0), "F.Fab", true, &uuid()));
+            for (name, value) in &footprint.properties {
+                output.push_str(&self.format_property(name, value, (0.0, 0.0), "F.Fab", true, &uuid()));
+            }
+        } else if self.version == KicadVersion::V6 {
+            for (name, value) in &footprint.properties {
+                output.push_str(&format!("  (property \"{}\" \"{}\")\n", escape(name), escape(value)));
+            }
+        }
+
+        // Footprint-wide pad defaults
+        let settings = &footprint.settings;
+        if let Some(margin) = settings.mask_margin {
+            output.push_str(&format!("  (solder_mask_margin {:.4})\n", margin));
+        }
+        if let Some(margin) = settings.paste_margin {
+            output.push_str(&format!("  (solder_paste_margin {:.4})\n", margin));
+        }
+        if let Some(ratio) = settings.paste_ratio {
+            output.push_str(&format!("  (solder_paste_ratio {:.4})\n", ratio));
+        }
+        if let Some(zone_connect) = settings.zone_connect {
+            output.push_str(&format!("  (zone_connect {})\n", zone_connect.to_kicad()));
+        }
+        if let Some(width) = settings.thermal_width {
+            output.push_str(&format!("  (thermal_width {:.4})\n", width));
+        }
+        if let Some(gap) = settings.thermal_gap {
+            output.push_str(&format!("  (thermal_gap {:.4})\n", gap));
+        }
+
+        // KiCad 5 has no through_hole attribute; leaving it out means the same there
+        match (self.version, footprint.attribute()) {
+            (KicadVersion::V5, Some(FootprintAttribute::ThroughHole)) | (_, None) => {}
+            (_, Some(attribute)) => output.push_str(&format!("  (attr {})\n", attribute.to_kicad())),
+        }
+
+        let thickness = TEXT_SIZE_MM * 0.15;
+        if !properties {
+            output.push_str(&format!(
+                "  (fp_text reference \"REF**\" (at {:.4} {:.4}) (layer {})\n",
+                reference_at.0,
+                reference_at.1,
+                self.layer("F.SilkS")
+            ));
+            output.push_str(&format!("    (effects (font (size {0} {0}) (thickness {1})))\n", TEXT_SIZE_MM, thickness));
+            output.push_str("  )\n");
+
+            output.push_str(&format!(
+                "  (fp_text value \"{}\" (at {:.4} {:.4}) (layer {})\n",
+                footprint.name,
+                value_at.0,
+                value_at.1,
+                self.layer("F.Fab")
+            ));
+            output.push_str(&format!("    (effects (font (size {0} {0}) (thickness {1})))\n", TEXT_SIZE_MM, thickness));
+            output.push_str("  )\n");
+        }
 
-        output.push_str(&format!("  (fp_text value \"{}\" (at 0 2.5) (layer \"F.Fab\")\n", footprint.name));
-        output.push_str("    (effects (font (size 1 1) (thickness 0.15)))\n");
+        let fab_size = placement.fab_reference_size;
+        let reference_variable = match self.version {
+            KicadVersion::V5 => "%R",
+            KicadVersion::V6 | KicadVersion::V8 | KicadVersion::V9 => "${REFERENCE}",
+        };
+        output.push_str(&format!(
+            "  (fp_text user \"{}\" (at {:.4} {:.4}) (layer {}){}\n",
+            reference_variable,
+            self.converter.px_to_mm(placement.fab_reference.0),
+            self.converter.px_to_mm(placement.fab_reference.1),
+            self.layer("F.Fab"),
+            uuid()
+        ));
+        output.push_str(&format!(
+            "    (effects (font (size {0:.2} {0:.2}) (thickness {1:.3})))\n",
+            fab_size,
+            fab_size * 0.15
+        ));
         output.push_str("  )\n");
 
         // Pads
         for pad in &footprint.pads {
-            output.push_str(&self.format_pad(pad));
+            output.push_str(&self.format_pad(pad, &uuid()));
         }
 
         // Lines
         for line in &footprint.lines {
-            output.push_str(&self.format_line(line));
+            match self.version {
+                KicadVersion::V5 => output.push_str(&self.format_line_v5(line)),
+                KicadVersion::V6 | KicadVersion::V8 | KicadVersion::V9 => {
+                    output.push_str(&self.format_line(line, &uuid()))
+                }
+            }
         }
 
         // Circles
         for circle in &footprint.circles {
-            output.push_str(&self.format_circle(circle));
+            match self.version {
+                KicadVersion::V5 => output.push_str(&self.format_circle_v5(circle)),
+                KicadVersion::V6 | KicadVersion::V8 | KicadVersion::V9 => {
+                    output.push_str(&self.format_circle(circle, &uuid()))
+                }
+            }
         }
 
         // Arcs
         for arc in &footprint.arcs {
-            output.push_str(&self.format_arc(arc));
+            match self.version {
+                KicadVersion::V5 => output.push_str(&self.format_arc_v5(arc)),
+                KicadVersion::V6 | KicadVersion::V8 | KicadVersion::V9 => {
+                    output.push_str(&self.format_arc(arc, &uuid()))
+                }
+            }
         }
 
         // Texts
         for text in &footprint.texts {
-            output.push_str(&self.format_text(text));
+            output.push_str(&self.format_text(text, &uuid()));
+        }
+
+        if self.version == KicadVersion::V9 {
+            output.push_str("  (embedded_fonts no)\n");
         }
 
         // 3D model
@@ -65,7 +217,20 @@ impl FootprintExporter {
         Ok(output)
     }
 
-    fn format_pad(&self, pad: &KiPad) -> String {
+    /// KiCad 8 footprint property; fields other than reference and value are hidden
+    fn format_property(&self, name: &str, value: &str, at: (f64, f64), layer: &str, hidden: bool, uuid: &str) -> String {
+        let flags = if hidden { " (unlocked yes)" } else { "" };
+        let hide = if hidden { " (hide yes)" } else { "" };
+        let mut output = format!(
+            "  (property \"{}\" \"{}\" (at {:.4} {:.4} 0){} (layer \"{}\"){}{}\n",
+            escape(name), escape(value), at.0, at.1, flags, layer, hide, uuid
+        );
+        output.push_str(&format!("    (effects (font (size {0} {0}) (thickness {1})))\n", TEXT_SIZE_MM, TEXT_SIZE_MM * 0.15));
+        output.push_str("  )\n");
+        output
+    }
+
+    fn format_pad(&self, pad: &KiPad, uuid: &str) -> String {
         let x = self.converter.px_to_mm(pad.pos_x);
         let y = self.converter.px_to_mm(pad.pos_y);  // No flip_y for footprints
         let size_x = self.converter.px_to_mm(pad.size_x);
@@ -89,9 +254,9 @@ impl FootprintExporter {
         // Layers
         output.push_str(" (layers");
         for layer in &pad.layers {
-            output.push_str(&format!(" \"{}\"", layer));
+            output.push_str(&format!(" {}", self.layer(layer)));
         }
-        output.push_str(")");
+        output.push(')');
 
         // Drill
         if let Some(drill) = &pad.drill {
@@ -106,17 +271,68 @@ impl FootprintExporter {
             }
         }
 
-        // Polygon (for custom pads)
-        if let Some(polygon) = &pad.polygon {
-            output.push_str(polygon);
+        // Corner rounding and chamfers
+        if pad.shape == PadShape::RoundRect {
+            output.push_str(&format!(" (roundrect_rratio {:.4})", pad.roundrect_ratio));
+        }
+        if let Some(chamfer) = &pad.chamfer {
+            output.push_str(&format!(" (chamfer_ratio {:.4}) (chamfer", chamfer.ratio));
+            for corner in &chamfer.corners {
+                output.push_str(&format!(" {}", corner.to_kicad()));
+            }
+            output.push(')');
+        }
+
+        // Mask, paste and zone settings
+        let settings = &pad.settings;
+        if let Some(margin) = settings.mask_margin {
+            output.push_str(&format!(" (solder_mask_margin {:.4})", margin));
+        }
+        if let Some(margin) = settings.paste_margin {
+            output.push_str(&format!(" (solder_paste_margin {:.4})", margin));
+        }
+        if let Some(ratio) = settings.paste_ratio {
+            output.push_str(&format!(" (solder_paste_margin_ratio {:.4})", ratio));
+        }
+        if let Some(zone_connect) = settings.zone_connect {
+            output.push_str(&format!(" (zone_connect {})", zone_connect.to_kicad()));
+        }
+        if let Some(width) = settings.thermal_width {
+            let token = match self.version {
+                KicadVersion::V5 => "thermal_width",
+                KicadVersion::V6 | KicadVersion::V8 | KicadVersion::V9 => "thermal_bridge_width",
+            };
+            output.push_str(&format!(" ({} {:.4})", token, width));
+        }
+        if let Some(gap) = settings.thermal_gap {
+            output.push_str(&format!(" (thermal_gap {:.4})", gap));
         }
 
+        // Custom pad primitives (outline relative to the pad, before rotation)
+        if let Some(custom) = &pad.custom {
+            output.push_str(&format!(
+                "\n    (options (clearance outline) (anchor {}))\n    (primitives\n      (gr_poly (pts",
+                custom.anchor.to_kicad()
+            ));
+            for (x, y) in &custom.outline {
+                output.push_str(&format!(" (xy {:.4} {:.4})", self.converter.px_to_mm(*x), self.converter.px_to_mm(*y)));
+            }
+            // KiCad 5 fills pad primitives implicitly
+            match self.version {
+                KicadVersion::V5 => output.push_str(") (width 0))\n    )\n  "),
+                KicadVersion::V6 | KicadVersion::V8 | KicadVersion::V9 => {
+                    output.push_str(") (width 0) (fill yes))\n    )\n  ")
+                }
+            }
+        }
+
+        output.push_str(uuid);
         output.push_str(")\n");
 
         output
     }
 
-    fn format_line(&self, line: &KiLine) -> String {
+    fn format_line(&self, line: &KiLine, uuid: &str) -> String {
         let start_x = self.converter.px_to_mm(line.start_x);
         let start_y = self.converter.px_to_mm(line.start_y);  // No flip_y for footprints
         let end_x = self.converter.px_to_mm(line.end_x);
@@ -124,12 +340,12 @@ impl FootprintExporter {
         let width = self.converter.px_to_mm(line.width);
 
         format!(
-            "  (fp_line (start {:.4} {:.4}) (end {:.4} {:.4})\n    (stroke (width {:.4}) (type solid)) (layer \"{}\")\n  )\n",
-            start_x, start_y, end_x, end_y, width, line.layer
+            "  (fp_line (start {:.4} {:.4}) (end {:.4} {:.4})\n    (stroke (width {:.4}) (type solid)) (layer \"{}\"){}\n  )\n",
+            start_x, start_y, end_x, end_y, width, line.layer, uuid
         )
     }
 
-    fn format_circle(&self, circle: &KiCircle) -> String {
+    fn format_circle(&self, circle: &KiCircle, uuid: &str) -> String {
         let center_x = self.converter.px_to_mm(circle.center_x);
         let center_y = self.converter.px_to_mm(circle.center_y);  // No flip_y for footprints
         let end_x = self.converter.px_to_mm(circle.end_x);
@@ -139,12 +355,12 @@ impl FootprintExporter {
         let fill = if circle.fill { "solid" } else { "none" };
 
         format!(
-            "  (fp_circle (center {:.4} {:.4}) (end {:.4} {:.4})\n    (stroke (width {:.4}) (type solid)) (fill {}) (layer \"{}\")\n  )\n",
-            center_x, center_y, end_x, end_y, width, fill, circle.layer
+            "  (fp_circle (center {:.4} {:.4}) (end {:.4} {:.4})\n    (stroke (width {:.4}) (type solid)) (fill {}) (layer \"{}\"){}\n  )\n",
+            center_x, center_y, end_x, end_y, width, fill, circle.layer, uuid
         )
     }
 
-    fn format_arc(&self, arc: &KiArc) -> String {
+    fn format_arc(&self, arc: &KiArc, uuid: &str) -> String {
         let start_x = self.converter.px_to_mm(arc.start_x);
         let start_y = self.converter.px_to_mm(arc.start_y);  // No flip_y for footprints
         let mid_x = self.converter.px_to_mm(arc.mid_x);
@@ -154,12 +370,80 @@ impl FootprintExporter {
         let width = self.converter.px_to_mm(arc.width);
 
         format!(
-            "  (fp_arc (start {:.4} {:.4}) (mid {:.4} {:.4}) (end {:.4} {:.4})\n    (stroke (width {:.4}) (type solid)) (layer \"{}\")\n  )\n",
-            start_x, start_y, mid_x, mid_y, end_x, end_y, width, arc.layer
+            "  (fp_arc (start {:.4} {:.4}) (mid {:.4} {:.4}) (end {:.4} {:.4})\n    (stroke (width {:.4}) (type solid)) (layer \"{}\"){}\n  )\n",
+            start_x, start_y, mid_x, mid_y, end_x, end_y, width, arc.layer, uuid
+        )
+    }
+
+    fn format_line_v5(&self, line: &KiLine) -> String {
+        format!(
+            "  (fp_line (start {:.4} {:.4}) (end {:.4} {:.4}) (layer {}) (width {:.4}))\n",
+            self.converter.px_to_mm(line.start_x),
+            self.converter.px_to_mm(line.start_y),
+            self.converter.px_to_mm(line.end_x),
+            self.converter.px_to_mm(line.end_y),
+            line.layer,
+            self.converter.px_to_mm(line.width)
         )
     }
 
-    fn format_text(&self, text: &KiText) -> String {
+    fn format_circle_v5(&self, circle: &KiCircle) -> String {
+        let center_x = self.converter.px_to_mm(circle.center_x);
+        let center_y = self.converter.px_to_mm(circle.center_y);
+        let mut radius = self.converter.px_to_mm(
+            (circle.end_x - circle.center_x).hypot(circle.end_y - circle.center_y)
+        );
+        let mut width = self.converter.px_to_mm(circle.width);
+
+        // KiCad 5 circles cannot be filled: draw a stroke that reaches the center instead
+        if circle.fill {
+            width = radius + width / 2.0;
+            radius = width / 2.0;
+        }
+
+        format!(
+            "  (fp_circle (center {:.4} {:.4}) (end {:.4} {:.4}) (layer {}) (width {:.4}))\n",
+            center_x, center_y, center_x + radius, center_y, circle.layer, width
+        )
+    }
+
+    /// KiCad 5 arcs are stored as center, start point and sweep angle
+    /// (positive is clockwise on screen, i.e. increasing angle with y pointing down)
+    fn format_arc_v5(&self, arc: &KiArc) -> String {
+        let start = (arc.start_x, arc.start_y);
+        let mid = (arc.mid_x, arc.mid_y);
+        let end = (arc.end_x, arc.end_y);
+
+        let Some((cx, cy, _)) = self.converter.arc_center(start, mid, end) else {
+            // Degenerate arc, keep it as a straight segment
+            return self.format_line_v5(&KiLine {
+                start_x: arc.start_x,
+                start_y: arc.start_y,
+                end_x: arc.end_x,
+                end_y: arc.end_y,
+                width: arc.width,
+                layer: arc.layer.clone(),
+            });
+        };
+
+        let angle = |(x, y): (f64, f64)| (y - cy).atan2(x - cx).to_degrees();
+        let sweep_end = (angle(end) - angle(start)).rem_euclid(360.0);
+        let sweep_mid = (angle(mid) - angle(start)).rem_euclid(360.0);
+        let sweep = if sweep_mid <= sweep_end { sweep_end } else { sweep_end - 360.0 };
+
+        format!(
+            "  (fp_arc (start {:.4} {:.4}) (end {:.4} {:.4}) (angle {:.4}) (layer {}) (width {:.4}))\n",
+            self.converter.px_to_mm(cx),
+            self.converter.px_to_mm(cy),
+            self.converter.px_to_mm(arc.start_x),
+            self.converter.px_to_mm(arc.start_y),
+            sweep,
+            arc.layer,
+            self.converter.px_to_mm(arc.width)
+        )
+    }
+
+    fn format_text(&self, text: &KiText, uuid: &str) -> String {
         let x = self.converter.px_to_mm(text.pos_x);
         let y = self.converter.px_to_mm(text.pos_y);  // No flip_y for footprints
         let size = self.converter.px_to_mm(text.size);
@@ -173,11 +457,27 @@ impl FootprintExporter {
         } else {
             String::new()
         }) + &format!(
-            ") (layer \"{}\")\n    (effects (font (size {:.4} {:.4}) (thickness {:.4})))\n  )\n",
-            text.layer, size, size, thickness
+            ") (layer {}){}\n    (effects (font (size {:.4} {:.4}) (thickness {:.4})))\n  )\n",
+            self.layer(&text.layer), uuid, size, size, thickness
         )
     }
 
+    /// ` (uuid ...)` token for KiCad 8 and later, derived from the seed and item index
+    fn uuid(&self, seed: &str, index: usize) -> String {
+        match self.version {
+            KicadVersion::V8 | KicadVersion::V9 => format!(" (uuid \"{}\")", stable_uuid(&format!("{}/{}", seed, index))),
+            KicadVersion::V5 | KicadVersion::V6 => String::new(),
+        }
+    }
+
+    /// Layer name as written by the target version (KiCad 5 leaves it unquoted)
+    fn layer(&self, name: &str) -> String {
+        match self.version {
+            KicadVersion::V5 => name.to_string(),
+            KicadVersion::V6 | KicadVersion::V8 | KicadVersion::V9 => format!("\"{}\"", name),
+        }
+    }
+
     fn format_3d_model(&self, model: &Ki3dModel) -> String {
         format!(
             "  (model \"{}\"\n    (offset (xyz {:.4} {:.4} {:.4}))\n    (scale (xyz {:.4} {:.4} {:.4}))\n    (rotate (xyz {:.4} {:.4} {:.4}))\n  )\n",
@@ -191,6 +491,114 @@ impl FootprintExporter {
 
 impl Default for FootprintExporter {
     fn default() -> Self {
-        Self::new()
+        Self::new(KicadVersion::V6)
+    }
+}
+
+/// Quote-safe string for an s-expression: backslashes first, then double quotes
+pub(crate) fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arc(start: (f64, f64), mid: (f64, f64), end: (f64, f64)) -> KiArc {
+        KiArc {
+            start_x: start.0,
+            start_y: start.1,
+            mid_x: mid.0,
+            mid_y: mid.1,
+            end_x: end.0,
+            end_y: end.1,
+            width: 1.0,
+            layer: "F.SilkS".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_v5_arc_sweep_follows_mid_point() {
+        let exporter = FootprintExporter::new(KicadVersion::V5);
+
+        // Through the lower half (y down): clockwise on screen, positive angle
+        let line = exporter.format_arc_v5(&arc((10.0, 0.0), (0.0, 10.0), (-10.0, 0.0)));
+        assert!(line.starts_with("  (fp_arc (start 0.0000 0.0000) (end 2.5400 0.0000) (angle 180.0000)"));
+
+        // Quarter arc through the upper half: counter-clockwise, negative angle
+        let half = 10.0 / 2f64.sqrt();
+        let line = exporter.format_arc_v5(&arc((10.0, 0.0), (half, -half), (0.0, -10.0)));
+        assert!(line.contains("(angle -90.0000) (layer F.SilkS) (width 0.2540)"));
+    }
+
+    #[test]
+    fn test_v9_properties_and_stable_uuids() {
+        let footprint = KiFootprint {
+            name: "R0603_C25804".to_string(),
+            lcsc_id: "C25804".to_string(),
+            datasheet: String::new(),
+            description: "R0603 0603 package, LCSC part C25804".to_string(),
+            tags: vec!["0603".to_string(), "C25804".to_string()],
+            properties: vec![("LCSC".to_string(), "C25804".to_string())],
+            pads: Vec::new(),
+            tracks: Vec::new(),
+            circles: Vec::new(),
+            arcs: Vec::new(),
+            texts: Vec::new(),
+            lines: vec![KiLine {
+                start_x: 0.0,
+                start_y: 0.0,
+                end_x: 10.0,
+                end_y: 0.0,
+                width: 0.5,
+                layer: "F.SilkS".to_string(),
+            }],
+            model_3d: None,
+            settings: PadSettings::default(),
+        };
+
+        let exporter = FootprintExporter::new(KicadVersion::V9);
+        let output = exporter.export(&footprint).unwrap();
+        assert!(output.starts_with("(footprint \"R0603_C25804\" (version 20241229)"));
+        assert!(output.contains("(property \"Reference\" \"REF**\""));
+        assert!(output.contains("(property \"LCSC\" \"C25804\""));
+        assert!(output.contains("(embedded_fonts no)"));
+        assert!(!output.contains("fp_text reference"));
+        assert!(output.contains(&format!("(uuid \"{}\")", stable_uuid("C25804/1"))));
+        assert_eq!(output, exporter.export(&footprint).unwrap());
+
+        let v6 = FootprintExporter::new(KicadVersion::V6).export(&footprint).unwrap();
+        assert!(!v6.contains("(uuid") && !v6.contains("embedded_fonts"));
+        assert!(v6.contains("  (descr \"R0603 0603 package, LCSC part C25804\")\n  (tags \"0603 C25804\")\n"));
+        assert!(v6.contains("  (property \"LCSC\" \"C25804\")\n"));
+    }
+
+    #[test]
+    fn test_description_is_escaped() {
+        let footprint = KiFootprint {
+            name: "SW_C318884".to_string(),
+            description: r#"6x6mm 1/4" switch, C:\parts"#.to_string(),
+            ..KiFootprint::default()
+        };
+
+        let output = FootprintExporter::new(KicadVersion::V6).export(&footprint).unwrap();
+        assert!(output.contains(r#"  (descr "6x6mm 1/4\" switch, C:\\parts")"#));
+    }
+
+    #[test]
+    fn test_tags_and_properties_are_escaped() {
+        let footprint = KiFootprint {
+            name: "SW_C318884".to_string(),
+            tags: vec![r#"1/4""#.to_string()],
+            properties: vec![("Note".to_string(), r#"6x6mm 1/4" cap"#.to_string())],
+            ..KiFootprint::default()
+        };
+
+        let v6 = FootprintExporter::new(KicadVersion::V6).export(&footprint).unwrap();
+        assert!(v6.contains(r#"  (tags "1/4\"")"#));
+        assert!(v6.contains(r#"  (property "Note" "6x6mm 1/4\" cap")"#));
+
+        let v9 = FootprintExporter::new(KicadVersion::V9).export(&footprint).unwrap();
+        assert!(v9.contains(r#"  (property "Note" "6x6mm 1/4\" cap" "#));
     }
 }
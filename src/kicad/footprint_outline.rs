@@ -0,0 +1,349 @@
+use crate::kicad::footprint::{BBox, KiFootprint, KiLine, PX_PER_MM};
+use std::str::FromStr;
+
+/// Courtyard outlines are placed on a 0.01 mm grid
+const COURTYARD_GRID_MM: f64 = 0.01;
+const COURTYARD_LINE_WIDTH_MM: f64 = 0.05;
+const FAB_LINE_WIDTH_MM: f64 = 0.1;
+
+/// Largest pin-1 chamfer on the fab outline
+const FAB_CHAMFER_MAX_MM: f64 = 1.0;
+
+/// Reference/value text height and distance from the footprint extents (in mm)
+pub const TEXT_SIZE_MM: f64 = 1.0;
+const TEXT_GAP_MM: f64 = 0.5;
+
+/// Size range of the ${REFERENCE} text on F.Fab in the KLC profile (in mm)
+const FAB_TEXT_MIN_MM: f64 = 0.5;
+const FAB_TEXT_MAX_MM: f64 = 1.0;
+
+/// Where the reference and value texts go
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextLayout {
+    /// Above and below the pads and graphics, fixed text size
+    #[default]
+    Default,
+    /// KiCad Library Convention: above and below the courtyard, with the F.Fab
+    /// reference scaled to fit the body
+    Klc,
+}
+
+impl FromStr for TextLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "default" => Ok(TextLayout::Default),
+            "klc" => Ok(TextLayout::Klc),
+            _ => Err(format!("expected 'default' or 'klc', got '{}'", s)),
+        }
+    }
+}
+
+/// Positions of the footprint texts (in px) and the F.Fab reference size (in mm)
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextPlacement {
+    pub reference: (f64, f64),
+    pub value: (f64, f64),
+    pub fab_reference: (f64, f64),
+    pub fab_reference_size: f64,
+}
+
+/// Extents of all pads, or None for a footprint without pads
+pub fn pad_bbox(footprint: &KiFootprint) -> Option<BBox> {
+    footprint.pads.iter()
+        .map(|pad| pad.bbox())
+        .reduce(|a, b| a.union(&b))
+}
+
+/// Extents of the graphic items on a layer, including their stroke width
+pub fn layer_bbox(footprint: &KiFootprint, layer: &str) -> Option<BBox> {
+    let lines = footprint.lines.iter()
+        .filter(|line| line.layer == layer)
+        .map(|line| {
+            BBox::new(line.start_x, line.start_y)
+                .union(&BBox::new(line.end_x, line.end_y))
+                .expand(line.width / 2.0)
+        });
+
+    let circles = footprint.circles.iter()
+        .filter(|circle| circle.layer == layer)
+        .map(|circle| {
+            let radius = (circle.end_x - circle.center_x).hypot(circle.end_y - circle.center_y);
+            BBox::around(circle.center_x, circle.center_y, radius, radius)
+                .expand(circle.width / 2.0)
+        });
+
+    // Arcs are approximated by their start, mid and end points
+    let arcs = footprint.arcs.iter()
+        .filter(|arc| arc.layer == layer)
+        .map(|arc| {
+            BBox::new(arc.start_x, arc.start_y)
+                .union(&BBox::new(arc.mid_x, arc.mid_y))
+                .union(&BBox::new(arc.end_x, arc.end_y))
+                .expand(arc.width / 2.0)
+        });
+
+    lines.chain(circles).chain(arcs).reduce(|a, b| a.union(&b))
+}
+
+/// Component body extents: the fab drawing if present, else the silkscreen, else the pads
+pub fn body_bbox(footprint: &KiFootprint) -> Option<BBox> {
+    layer_bbox(footprint, "F.Fab")
+        .or_else(|| layer_bbox(footprint, "F.SilkS"))
+        .or_else(|| pad_bbox(footprint))
+}
+
+/// Extents of pads and all graphics of the footprint
+pub fn footprint_bbox(footprint: &KiFootprint) -> Option<BBox> {
+    let mut layers: Vec<&str> = footprint.lines.iter().map(|line| line.layer.as_str())
+        .chain(footprint.circles.iter().map(|circle| circle.layer.as_str()))
+        .chain(footprint.arcs.iter().map(|arc| arc.layer.as_str()))
+        .collect();
+    layers.sort_unstable();
+    layers.dedup();
+
+    layers.into_iter()
+        .filter_map(|layer| layer_bbox(footprint, layer))
+        .chain(pad_bbox(footprint))
+        .reduce(|a, b| a.union(&b))
+}
+
+/// Place the reference above and the value below the footprint extents
+pub fn text_placement(footprint: &KiFootprint, layout: TextLayout) -> TextPlacement {
+    let extents = match layout {
+        TextLayout::Default => footprint_bbox(footprint),
+        TextLayout::Klc => layer_bbox(footprint, "F.CrtYd").or_else(|| footprint_bbox(footprint)),
+    }
+    .unwrap_or(BBox::new(0.0, 0.0));
+
+    let offset = (TEXT_GAP_MM + TEXT_SIZE_MM / 2.0) * PX_PER_MM;
+    let center_x = (extents.min_x + extents.max_x) / 2.0;
+
+    let body = body_bbox(footprint).unwrap_or(extents);
+    let fab_reference_size = match layout {
+        TextLayout::Default => TEXT_SIZE_MM,
+        TextLayout::Klc => {
+            // "${REFERENCE}" usually expands to 2-4 characters
+            let fit = (body.width() / PX_PER_MM / 3.0).min(body.height() / PX_PER_MM / 1.5);
+            fit.clamp(FAB_TEXT_MIN_MM, FAB_TEXT_MAX_MM)
+        }
+    };
+
+    TextPlacement {
+        reference: (center_x, extents.min_y - offset),
+        value: (center_x, extents.max_y + offset),
+        fab_reference: ((body.min_x + body.max_x) / 2.0, (body.min_y + body.max_y) / 2.0),
+        fab_reference_size,
+    }
+}
+
+/// Add a rectangular F.CrtYd outline around pads and body, `clearance` mm away
+pub fn add_courtyard(footprint: &mut KiFootprint, clearance: f64) {
+    let bbox = match (pad_bbox(footprint), body_bbox(footprint)) {
+        (Some(pads), Some(body)) => pads.union(&body),
+        (Some(bbox), None) | (None, Some(bbox)) => bbox,
+        (None, None) => return,
+    };
+
+    // Round outward to the courtyard grid, working in mm
+    let grid = COURTYARD_GRID_MM;
+    let outward = |value: f64, up: bool| {
+        let mm = value / PX_PER_MM;
+        let snapped = if up { (mm / grid - 1e-6).ceil() } else { (mm / grid + 1e-6).floor() } * grid;
+        snapped * PX_PER_MM
+    };
+
+    let expanded = bbox.expand(clearance * PX_PER_MM);
+    let courtyard = BBox {
+        min_x: outward(expanded.min_x, false),
+        min_y: outward(expanded.min_y, false),
+        max_x: outward(expanded.max_x, true),
+        max_y: outward(expanded.max_y, true),
+    };
+
+    let corners = [
+        (courtyard.min_x, courtyard.min_y),
+        (courtyard.max_x, courtyard.min_y),
+        (courtyard.max_x, courtyard.max_y),
+        (courtyard.min_x, courtyard.max_y),
+    ];
+    push_outline(footprint, &corners, COURTYARD_LINE_WIDTH_MM * PX_PER_MM, "F.CrtYd");
+}
+
+/// Add an F.Fab body outline with a chamfered pin-1 corner.
+///
+/// Footprints that already carry fab graphics from EasyEDA are left unchanged.
+pub fn add_fab_outline(footprint: &mut KiFootprint) {
+    if layer_bbox(footprint, "F.Fab").is_some() {
+        return;
+    }
+    let Some(body) = body_bbox(footprint) else {
+        return;
+    };
+
+    let chamfer = (FAB_CHAMFER_MAX_MM * PX_PER_MM).min(body.width().min(body.height()) * 0.25);
+
+    // Corners in drawing order, starting top-left and going clockwise
+    let corners = [
+        (body.min_x, body.min_y),
+        (body.max_x, body.min_y),
+        (body.max_x, body.max_y),
+        (body.min_x, body.max_y),
+    ];
+
+    let pin1 = footprint.pads.iter()
+        .find(|pad| pad.number == "1")
+        .or_else(|| footprint.pads.iter().find(|pad| !pad.number.is_empty()));
+
+    let Some(pin1) = pin1.filter(|_| chamfer > 0.0) else {
+        push_outline(footprint, &corners, FAB_LINE_WIDTH_MM * PX_PER_MM, "F.Fab");
+        return;
+    };
+
+    // Chamfer the corner closest to pin 1
+    let distance = |corner: &(f64, f64)| (corner.0 - pin1.pos_x).hypot(corner.1 - pin1.pos_y);
+    let nearest = (0..corners.len())
+        .min_by(|&a, &b| distance(&corners[a]).total_cmp(&distance(&corners[b])))
+        .unwrap_or(0);
+
+    let mut points = Vec::with_capacity(5);
+    for (idx, &(x, y)) in corners.iter().enumerate() {
+        if idx != nearest {
+            points.push((x, y));
+            continue;
+        }
+
+        let prev = corners[(idx + corners.len() - 1) % corners.len()];
+        let next = corners[(idx + 1) % corners.len()];
+        points.push(toward((x, y), prev, chamfer));
+        points.push(toward((x, y), next, chamfer));
+    }
+
+    push_outline(footprint, &points, FAB_LINE_WIDTH_MM * PX_PER_MM, "F.Fab");
+}
+
+/// Point `distance` away from `from` in the direction of `to`
+fn toward(from: (f64, f64), to: (f64, f64), distance: f64) -> (f64, f64) {
+    let length = (to.0 - from.0).hypot(to.1 - from.1);
+    if length == 0.0 {
+        return from;
+    }
+    (
+        from.0 + (to.0 - from.0) / length * distance,
+        from.1 + (to.1 - from.1) / length * distance,
+    )
+}
+
+/// Add a closed outline through `points` as line segments
+fn push_outline(footprint: &mut KiFootprint, points: &[(f64, f64)], width: f64, layer: &str) {
+    for (idx, &(start_x, start_y)) in points.iter().enumerate() {
+        let (end_x, end_y) = points[(idx + 1) % points.len()];
+        footprint.lines.push(KiLine {
+            start_x,
+            start_y,
+            end_x,
+            end_y,
+            width,
+            layer: layer.to_string(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kicad::footprint::*;
+
+    fn pad(number: &str, x: f64, y: f64) -> KiPad {
+        KiPad {
+            number: number.to_string(),
+            pos_x: x,
+            pos_y: y,
+            size_x: 4.0,
+            size_y: 2.0,
+            layers: vec!["F.Cu".to_string()],
+            ..KiPad::default()
+        }
+    }
+
+    fn footprint(pads: Vec<KiPad>) -> KiFootprint {
+        KiFootprint { pads, ..KiFootprint::default() }
+    }
+
+    fn layer_lines<'a>(fp: &'a KiFootprint, layer: &str) -> Vec<&'a KiLine> {
+        fp.lines.iter().filter(|line| line.layer == layer).collect()
+    }
+
+    #[test]
+    fn test_courtyard_around_pads() {
+        let mut fp = footprint(vec![pad("1", -10.0, 0.0), pad("2", 10.0, 0.0)]);
+        add_courtyard(&mut fp, 0.25);
+
+        let lines = layer_lines(&fp, "F.CrtYd");
+        assert_eq!(lines.len(), 4);
+        // Pads span ±3.048 mm, plus clearance, rounded outward to 0.01 mm
+        let xs: Vec<f64> = lines.iter().map(|l| l.start_x / PX_PER_MM).collect();
+        assert!(xs.iter().any(|x| (x + 3.30).abs() < 1e-9));
+        assert!(xs.iter().any(|x| (x - 3.30).abs() < 1e-9));
+        assert_eq!(fp.attribute(), Some(FootprintAttribute::Smd));
+    }
+
+    #[test]
+    fn test_fab_outline_chamfers_pin1_corner() {
+        let mut fp = footprint(vec![pad("1", -10.0, 0.0), pad("2", 10.0, 0.0)]);
+        add_fab_outline(&mut fp);
+
+        let lines = layer_lines(&fp, "F.Fab");
+        assert_eq!(lines.len(), 5);
+        // Top-left corner is cut, so no segment starts there
+        assert!(!lines.iter().any(|l| l.start_x == -12.0 && l.start_y == -1.0));
+        assert!(lines.iter().any(|l| l.start_x == 12.0 && l.start_y == -1.0));
+
+        // Existing fab graphics are kept as they are
+        add_fab_outline(&mut fp);
+        assert_eq!(layer_lines(&fp, "F.Fab").len(), 5);
+    }
+
+    #[test]
+    fn test_text_placement() {
+        let mut fp = footprint(vec![pad("1", -10.0, 0.0), pad("2", 10.0, 0.0)]);
+
+        let placement = text_placement(&fp, TextLayout::Default);
+        let offset = (TEXT_GAP_MM + TEXT_SIZE_MM / 2.0) * PX_PER_MM;
+        assert!((placement.reference.1 - (-1.0 - offset)).abs() < 1e-9);
+        assert!((placement.value.1 - (1.0 + offset)).abs() < 1e-9);
+        assert_eq!(placement.fab_reference, (0.0, 0.0));
+
+        // KLC places the texts outside the courtyard and shrinks the fab text to the body
+        add_courtyard(&mut fp, 0.25);
+        let klc = text_placement(&fp, TextLayout::Klc);
+        assert!(klc.reference.1 < placement.reference.1);
+        assert_eq!(klc.fab_reference_size, FAB_TEXT_MIN_MM);
+    }
+
+    #[test]
+    fn test_courtyard_around_rotated_pads() {
+        let mut rotated = pad("2", 10.0, 0.0);
+        rotated.rotation = 90.0;
+        let mut fp = footprint(vec![pad("1", -10.0, 0.0), rotated]);
+        add_courtyard(&mut fp, 0.0);
+
+        // The rotated pad is 2 px wide and 4 px high
+        let bbox = layer_bbox(&fp, "F.CrtYd").unwrap().expand(-COURTYARD_LINE_WIDTH_MM * PX_PER_MM / 2.0);
+        assert!((bbox.max_x / PX_PER_MM - 2.80).abs() < 1e-9);
+        assert!((bbox.max_y / PX_PER_MM - 0.51).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_empty_footprint() {
+        let mut fp = footprint(Vec::new());
+        add_courtyard(&mut fp, 0.25);
+        add_fab_outline(&mut fp);
+
+        assert!(fp.lines.is_empty());
+        assert_eq!(fp.attribute(), None);
+        let placement = text_placement(&fp, TextLayout::Klc);
+        assert_eq!(placement.fab_reference, (0.0, 0.0));
+    }
+}
@@ -1,5 +1,5 @@
-/// KiCad layer mapping for EasyEDA footprints
-/// Based on easyeda2kicad.py layer mapping
+//! KiCad layer mapping for EasyEDA footprints
+//! Based on easyeda2kicad.py layer mapping
 
 /// Map EasyEDA layer ID to KiCad layer name for general graphics
 pub fn map_layer(layer_id: i32) -> String {
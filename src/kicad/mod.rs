@@ -1,19 +1,39 @@
 pub mod symbol;
 pub mod footprint;
 pub mod symbol_exporter;
+pub mod symbol_grid;
 pub mod footprint_exporter;
+pub mod footprint_outline;
+pub mod pad_shape;
+pub mod custom_pad;
+pub mod paste;
+pub mod silk_clip;
 pub mod model_exporter;
+pub mod step;
+pub mod body;
 pub mod layers;
 
-pub use symbol::{KiSymbol, KiPin, KiRectangle, KiCircle, KiPolyline, PinType, PinStyle};
+pub use symbol::{
+    KiSymbol, KiPin, KiPinAlternate, KiRectangle, KiCircle, KiPolyline, KiBezier,
+    PinType, PinStyle, PinTextLayout, FillType,
+};
 pub use footprint::{
     KiFootprint, KiPad, KiTrack, KiLine, KiText, Ki3dModel, Drill,
-    PadType, PadShape,
+    PadType, PadShape, PadChamfer, PadCorner, CustomPad, CustomPadAnchor, FootprintAttribute, BBox,
+    PadSettings, ZoneConnect,
     KiCircle as FootprintKiCircle,
     KiArc as FootprintKiArc,
 };
-pub use symbol::KiArc as SymbolKiArc;
+pub use symbol::{KiArc as SymbolKiArc, KiText as SymbolKiText};
 pub use symbol_exporter::SymbolExporter;
+pub use symbol_grid::{snap_to_grid, SnapReport};
 pub use footprint_exporter::FootprintExporter;
+pub use footprint_outline::{add_courtyard, add_fab_outline, text_placement, TextLayout, TextPlacement};
+pub use custom_pad::{anchor_fits, build_custom_pad, CustomPadLayout};
+pub use paste::{window_exposed_pads, PasteGrid};
+pub use silk_clip::{clip_silkscreen, SilkClipReport};
+pub use pad_shape::{apply_roundrect_policy, detect_polygon_pad, PolygonPadShape, RoundRectPolicy};
 pub use model_exporter::ModelExporter;
+pub use step::{check_model_placement, inspect_step, StepInfo};
+pub use body::ExtrudedBody;
 pub use layers::*;
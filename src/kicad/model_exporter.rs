@@ -1,6 +1,23 @@
 use crate::error::{KicadError, Result};
+use crate::kicad::step::{inspect_step, StepInfo};
+use serde_json::json;
 use std::collections::HashMap;
 
+type Vertex = (f64, f64, f64);
+
+/// OBJ coordinates are parsed into VRML units (0.1 inch); glTF output is in mm
+const WRL_UNIT_TO_MM: f64 = 2.54;
+
+/// GLB container constants (glTF 2.0 binary format)
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_VERSION: u32 = 2;
+const GLB_CHUNK_JSON: u32 = 0x4E4F_534A;
+const GLB_CHUNK_BIN: u32 = 0x004E_4942;
+const GL_ARRAY_BUFFER: u32 = 34962;
+const GL_ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const GL_FLOAT: u32 = 5126;
+const GL_UNSIGNED_INT: u32 = 5125;
+
 pub struct ModelExporter;
 
 impl ModelExporter {
@@ -8,28 +25,15 @@ impl ModelExporter {
         Self
     }
 
-    /// Convert OBJ format to VRML (.wrl) format
+    /// Convert OBJ format to VRML (.wrl) format, one shape per material group
     pub fn obj_to_wrl(&self, obj_data: &[u8]) -> Result<String> {
         let obj_str = String::from_utf8_lossy(obj_data);
 
         // Parse OBJ file
         let vertices = self.parse_obj_vertices(&obj_str)?;
-        let faces = self.parse_obj_faces(&obj_str)?;
+        let groups = self.parse_obj_faces(&obj_str)?;
         let materials = self.parse_obj_materials(&obj_str);
 
-        // Optimize vertices (remove duplicates)
-        let (optimized_vertices, optimized_faces) = self.optimize_vertices(&vertices, &faces);
-
-        // Get material properties (use first material or default)
-        let material = materials.values().next().cloned().unwrap_or_else(|| Material {
-            name: "default".to_string(),
-            ambient: (0.2, 0.2, 0.2),
-            diffuse: (0.8, 0.8, 0.8),
-            specular: (0.5, 0.5, 0.5),
-            shininess: 0.5,
-            transparency: 1.0,
-        });
-
         // Generate VRML output
         let mut output = String::new();
 
@@ -37,6 +41,26 @@ impl ModelExporter {
         output.push_str("#VRML V2.0 utf8\n");
         output.push_str("#Generated by e2k\n\n");
 
+        for group in &groups {
+            // Optimize vertices (remove duplicates) per shape
+            let (optimized_vertices, optimized_faces) = self.optimize_vertices(&vertices, &group.faces);
+            if optimized_faces.is_empty() {
+                continue;
+            }
+
+            let material = group.material.as_ref()
+                .and_then(|name| materials.get(name))
+                .cloned()
+                .unwrap_or_default();
+            output.push_str(&self.format_wrl_shape(&material, &optimized_vertices, &optimized_faces));
+        }
+
+        Ok(output)
+    }
+
+    fn format_wrl_shape(&self, material: &Material, vertices: &[Vertex], faces: &[Vec<i32>]) -> String {
+        let mut output = String::new();
+
         // Shape node
         output.push_str("Shape {\n");
         output.push_str("  appearance Appearance {\n");
@@ -58,12 +82,12 @@ impl ModelExporter {
         output.push_str("      point [\n");
 
         // Write vertices
-        for (i, vertex) in optimized_vertices.iter().enumerate() {
+        for (i, vertex) in vertices.iter().enumerate() {
             output.push_str(&format!("        {:.6} {:.6} {:.6}", vertex.0, vertex.1, vertex.2));
-            if i < optimized_vertices.len() - 1 {
+            if i < vertices.len() - 1 {
                 output.push_str(",\n");
             } else {
-                output.push_str("\n");
+                output.push('\n');
             }
         }
 
@@ -72,7 +96,7 @@ impl ModelExporter {
 
         // Write faces
         output.push_str("    coordIndex [\n");
-        for (i, face) in optimized_faces.iter().enumerate() {
+        for (i, face) in faces.iter().enumerate() {
             output.push_str("      ");
             for (j, idx) in face.iter().enumerate() {
                 output.push_str(&format!("{}", idx));
@@ -81,10 +105,10 @@ impl ModelExporter {
                 }
             }
             output.push_str(", -1");
-            if i < optimized_faces.len() - 1 {
+            if i < faces.len() - 1 {
                 output.push_str(",\n");
             } else {
-                output.push_str("\n");
+                output.push('\n');
             }
         }
         output.push_str("    ]\n");
@@ -93,14 +117,158 @@ impl ModelExporter {
         output.push_str("  }\n");
         output.push_str("}\n");
 
-        Ok(output)
+        output
+    }
+
+    /// Convert OBJ format to binary glTF (.glb) with one PBR material per group.
+    ///
+    /// Positions are written in millimetres; polygons are triangulated as fans.
+    pub fn obj_to_gltf(&self, obj_data: &[u8]) -> Result<Vec<u8>> {
+        let obj_str = String::from_utf8_lossy(obj_data);
+
+        let vertices = self.parse_obj_vertices(&obj_str)?;
+        let groups = self.parse_obj_faces(&obj_str)?;
+        let materials = self.parse_obj_materials(&obj_str);
+
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut buffer_views = Vec::new();
+        let mut accessors = Vec::new();
+        let mut gltf_materials = Vec::new();
+        let mut primitives = Vec::new();
+
+        for group in &groups {
+            let (group_vertices, group_faces) = self.optimize_vertices(&vertices, &group.faces);
+            let indices: Vec<u32> = group_faces.iter()
+                .filter(|face| face.len() >= 3)
+                .flat_map(|face| (1..face.len() - 1).flat_map(move |i| [face[0], face[i], face[i + 1]]))
+                .map(|idx| idx as u32)
+                .collect();
+            if indices.is_empty() {
+                continue;
+            }
+
+            // Vertex positions
+            let mut min = [f32::MAX; 3];
+            let mut max = [f32::MIN; 3];
+            let positions_offset = buffer.len();
+            for vertex in &group_vertices {
+                let position = [vertex.0, vertex.1, vertex.2].map(|v| (v * WRL_UNIT_TO_MM) as f32);
+                for axis in 0..3 {
+                    min[axis] = min[axis].min(position[axis]);
+                    max[axis] = max[axis].max(position[axis]);
+                    buffer.extend_from_slice(&position[axis].to_le_bytes());
+                }
+            }
+            buffer_views.push(json!({
+                "buffer": 0,
+                "byteOffset": positions_offset,
+                "byteLength": buffer.len() - positions_offset,
+                "target": GL_ARRAY_BUFFER,
+            }));
+            accessors.push(json!({
+                "bufferView": buffer_views.len() - 1,
+                "componentType": GL_FLOAT,
+                "count": group_vertices.len(),
+                "type": "VEC3",
+                "min": min,
+                "max": max,
+            }));
+            let position_accessor = accessors.len() - 1;
+
+            // Triangle indices
+            let indices_offset = buffer.len();
+            for idx in &indices {
+                buffer.extend_from_slice(&idx.to_le_bytes());
+            }
+            buffer_views.push(json!({
+                "buffer": 0,
+                "byteOffset": indices_offset,
+                "byteLength": buffer.len() - indices_offset,
+                "target": GL_ELEMENT_ARRAY_BUFFER,
+            }));
+            accessors.push(json!({
+                "bufferView": buffer_views.len() - 1,
+                "componentType": GL_UNSIGNED_INT,
+                "count": indices.len(),
+                "type": "SCALAR",
+            }));
+
+            // PBR material from the OBJ colors: dielectric, roughness from shininess
+            let material = group.material.as_ref()
+                .and_then(|name| materials.get(name))
+                .cloned()
+                .unwrap_or_default();
+            let mut gltf_material = json!({
+                "name": material.name,
+                "pbrMetallicRoughness": {
+                    "baseColorFactor": [material.diffuse.0, material.diffuse.1, material.diffuse.2, material.transparency],
+                    "metallicFactor": 0.0,
+                    "roughnessFactor": (1.0 - material.shininess).clamp(0.0, 1.0),
+                },
+                "doubleSided": true,
+            });
+            if material.transparency < 1.0 {
+                gltf_material["alphaMode"] = json!("BLEND");
+            }
+            gltf_materials.push(gltf_material);
+
+            primitives.push(json!({
+                "attributes": { "POSITION": position_accessor },
+                "indices": accessors.len() - 1,
+                "material": gltf_materials.len() - 1,
+            }));
+        }
+
+        if primitives.is_empty() {
+            return Err(KicadError::ModelExport("OBJ model has no faces".to_string()).into());
+        }
+
+        let document = json!({
+            "asset": { "version": "2.0", "generator": "nlbn" },
+            "scene": 0,
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [{ "mesh": 0 }],
+            "meshes": [{ "primitives": primitives }],
+            "materials": gltf_materials,
+            "accessors": accessors,
+            "bufferViews": buffer_views,
+            "buffers": [{ "byteLength": buffer.len() }],
+        });
+
+        Ok(self.write_glb(&document.to_string(), buffer))
+    }
+
+    /// Pack the JSON document and binary buffer into a GLB container
+    fn write_glb(&self, document: &str, mut buffer: Vec<u8>) -> Vec<u8> {
+        // Chunks are 4-byte aligned: JSON padded with spaces, binary with zeros
+        let mut document = document.as_bytes().to_vec();
+        document.resize(document.len().next_multiple_of(4), b' ');
+        buffer.resize(buffer.len().next_multiple_of(4), 0);
+
+        let total_length = 12 + 8 + document.len() + 8 + buffer.len();
+        let mut output = Vec::with_capacity(total_length);
+        for word in [GLB_MAGIC, GLB_VERSION, total_length as u32] {
+            output.extend_from_slice(&word.to_le_bytes());
+        }
+        for (chunk_type, chunk) in [(GLB_CHUNK_JSON, &document), (GLB_CHUNK_BIN, &buffer)] {
+            output.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+            output.extend_from_slice(&chunk_type.to_le_bytes());
+            output.extend_from_slice(chunk);
+        }
+        output
     }
 
-    /// Export STEP file (just write binary data as-is)
+    /// Export STEP file (binary data as-is, after checking it is a complete STEP file)
     pub fn export_step(&self, step_data: &[u8]) -> Result<Vec<u8>> {
+        self.inspect_step(step_data)?;
         Ok(step_data.to_vec())
     }
 
+    /// Validate a STEP file and read its product name, units and bounding box
+    pub fn inspect_step(&self, step_data: &[u8]) -> Result<StepInfo> {
+        inspect_step(step_data)
+    }
+
     fn parse_obj_vertices(&self, obj: &str) -> Result<Vec<(f64, f64, f64)>> {
         let mut vertices = Vec::new();
 
@@ -124,12 +292,24 @@ impl ModelExporter {
         Ok(vertices)
     }
 
-    fn parse_obj_faces(&self, obj: &str) -> Result<Vec<Vec<i32>>> {
-        let mut faces = Vec::new();
+    /// Faces grouped by the material selected with `usemtl`, in file order
+    fn parse_obj_faces(&self, obj: &str) -> Result<Vec<FaceGroup>> {
+        let mut groups = vec![FaceGroup { material: None, faces: Vec::new() }];
+        let mut current = 0;
 
         for line in obj.lines() {
             let line = line.trim();
-            if line.starts_with("f ") {
+            if let Some(name) = line.strip_prefix("usemtl ") {
+                let name = Some(name.trim().to_string());
+                // Groups using the same material are merged into one shape
+                current = match groups.iter().position(|group| group.material == name) {
+                    Some(idx) => idx,
+                    None => {
+                        groups.push(FaceGroup { material: name, faces: Vec::new() });
+                        groups.len() - 1
+                    }
+                };
+            } else if line.starts_with("f ") {
                 let parts: Vec<&str> = line.split_whitespace().collect();
                 let mut face = Vec::new();
 
@@ -143,12 +323,13 @@ impl ModelExporter {
                 }
 
                 if !face.is_empty() {
-                    faces.push(face);
+                    groups[current].faces.push(face);
                 }
             }
         }
 
-        Ok(faces)
+        groups.retain(|group| !group.faces.is_empty());
+        Ok(groups)
     }
 
     fn parse_obj_materials(&self, obj: &str) -> HashMap<String, Material> {
@@ -169,66 +350,57 @@ impl ModelExporter {
                 if parts.len() >= 2 {
                     current_material = Some(Material {
                         name: parts[1].to_string(),
-                        ambient: (0.2, 0.2, 0.2),
-                        diffuse: (0.8, 0.8, 0.8),
-                        specular: (0.5, 0.5, 0.5),
-                        shininess: 0.5,
-                        transparency: 1.0,
+                        ..Material::default()
                     });
                 }
             } else if let Some(ref mut mat) = current_material {
                 if line.starts_with("Ka ") {
                     // Ambient color
                     let parts: Vec<&str> = line.split_whitespace().collect();
-                    if parts.len() >= 4 {
-                        if let (Ok(r), Ok(g), Ok(b)) = (
+                    if parts.len() >= 4
+                        && let (Ok(r), Ok(g), Ok(b)) = (
                             parts[1].parse::<f64>(),
                             parts[2].parse::<f64>(),
                             parts[3].parse::<f64>()
                         ) {
-                            mat.ambient = (r, g, b);
-                        }
+                        mat.ambient = (r, g, b);
                     }
                 } else if line.starts_with("Kd ") {
                     // Diffuse color
                     let parts: Vec<&str> = line.split_whitespace().collect();
-                    if parts.len() >= 4 {
-                        if let (Ok(r), Ok(g), Ok(b)) = (
+                    if parts.len() >= 4
+                        && let (Ok(r), Ok(g), Ok(b)) = (
                             parts[1].parse::<f64>(),
                             parts[2].parse::<f64>(),
                             parts[3].parse::<f64>()
                         ) {
-                            mat.diffuse = (r, g, b);
-                        }
+                        mat.diffuse = (r, g, b);
                     }
                 } else if line.starts_with("Ks ") {
                     // Specular color
                     let parts: Vec<&str> = line.split_whitespace().collect();
-                    if parts.len() >= 4 {
-                        if let (Ok(r), Ok(g), Ok(b)) = (
+                    if parts.len() >= 4
+                        && let (Ok(r), Ok(g), Ok(b)) = (
                             parts[1].parse::<f64>(),
                             parts[2].parse::<f64>(),
                             parts[3].parse::<f64>()
                         ) {
-                            mat.specular = (r, g, b);
-                        }
+                        mat.specular = (r, g, b);
                     }
                 } else if line.starts_with("d ") {
                     // Transparency (dissolve)
                     let parts: Vec<&str> = line.split_whitespace().collect();
-                    if parts.len() >= 2 {
-                        if let Ok(d) = parts[1].parse::<f64>() {
-                            mat.transparency = d;
-                        }
+                    if parts.len() >= 2
+                        && let Ok(d) = parts[1].parse::<f64>() {
+                        mat.transparency = d;
                     }
                 } else if line.starts_with("Ns ") {
                     // Shininess (specular exponent)
                     let parts: Vec<&str> = line.split_whitespace().collect();
-                    if parts.len() >= 2 {
-                        if let Ok(ns) = parts[1].parse::<f64>() {
-                            // Convert from OBJ range (0-1000) to VRML range (0-1)
-                            mat.shininess = (ns / 1000.0).min(1.0);
-                        }
+                    if parts.len() >= 2
+                        && let Ok(ns) = parts[1].parse::<f64>() {
+                        // Convert from OBJ range (0-1000) to VRML range (0-1)
+                        mat.shininess = (ns / 1000.0).min(1.0);
                     }
                 }
             }
@@ -242,30 +414,38 @@ impl ModelExporter {
         materials
     }
 
-    fn optimize_vertices(&self, vertices: &[(f64, f64, f64)], faces: &[Vec<i32>])
-        -> (Vec<(f64, f64, f64)>, Vec<Vec<i32>>) {
-        let mut link_dict: HashMap<i32, usize> = HashMap::new();
+    /// Keep only the vertices used by the faces and renumber them; faces that
+    /// reference a missing vertex (or use relative indices) are dropped
+    fn optimize_vertices(&self, vertices: &[Vertex], faces: &[Vec<i32>])
+        -> (Vec<Vertex>, Vec<Vec<i32>>) {
+        let mut link_dict: HashMap<usize, usize> = HashMap::new();
         let mut new_vertices = Vec::new();
         let mut new_faces = Vec::new();
+        let mut skipped = 0;
 
         for face in faces {
+            let valid = face.iter().all(|&idx| usize::try_from(idx).is_ok_and(|idx| idx < vertices.len()));
+            if !valid {
+                skipped += 1;
+                continue;
+            }
+
             let mut new_face = Vec::new();
             for &idx in face {
+                let idx = idx as usize;
                 let new_idx = *link_dict.entry(idx).or_insert_with(|| {
-                    let idx_usize = idx as usize;
-                    if idx_usize < vertices.len() {
-                        let new_idx = new_vertices.len();
-                        new_vertices.push(vertices[idx_usize]);
-                        new_idx
-                    } else {
-                        0
-                    }
+                    new_vertices.push(vertices[idx]);
+                    new_vertices.len() - 1
                 });
                 new_face.push(new_idx as i32);
             }
             new_faces.push(new_face);
         }
 
+        if skipped > 0 {
+            log::warn!("Skipped {} OBJ faces with invalid vertex indices", skipped);
+        }
+
         (new_vertices, new_faces)
     }
 }
@@ -285,3 +465,93 @@ struct Material {
     shininess: f64,
     transparency: f64,
 }
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            name: "default".to_string(),
+            ambient: (0.2, 0.2, 0.2),
+            diffuse: (0.8, 0.8, 0.8),
+            specular: (0.5, 0.5, 0.5),
+            shininess: 0.5,
+            transparency: 1.0,
+        }
+    }
+}
+
+/// Faces drawn with one material
+#[derive(Debug, Clone)]
+struct FaceGroup {
+    material: Option<String>,
+    faces: Vec<Vec<i32>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OBJ: &str = "\
+newmtl body
+Kd 0.1 0.1 0.1
+endmtl
+newmtl pin
+Kd 0.8 0.8 0.8
+endmtl
+v 0 0 0
+v 2.54 0 0
+v 0 2.54 0
+v 0 0 2.54
+usemtl body
+f 1 2 3
+usemtl pin
+f 1//1 2//1 4//1
+usemtl body
+f 1 3 4
+";
+
+    #[test]
+    fn test_wrl_shape_per_material() {
+        let wrl = ModelExporter::new().obj_to_wrl(OBJ.as_bytes()).unwrap();
+
+        assert_eq!(wrl.matches("Shape {").count(), 2);
+        assert!(wrl.contains("diffuseColor 0.10 0.10 0.10"));
+        assert!(wrl.contains("diffuseColor 0.80 0.80 0.80"));
+        // Coordinates are written in 0.1 inch units
+        assert!(wrl.contains("        1.000000 0.000000 0.000000"));
+
+        // The second body face lands in the first shape
+        let body = &wrl[..wrl.find("diffuseColor 0.80").unwrap()];
+        assert_eq!(body.matches(", -1").count(), 2);
+    }
+
+    #[test]
+    fn test_glb_container_and_materials() {
+        let glb = ModelExporter::new().obj_to_gltf(OBJ.as_bytes()).unwrap();
+        let word = |offset: usize| u32::from_le_bytes(glb[offset..offset + 4].try_into().unwrap()) as usize;
+
+        assert_eq!(&glb[0..4], b"glTF");
+        assert_eq!(word(8), glb.len());
+        assert_eq!(word(16), GLB_CHUNK_JSON as usize);
+
+        let json_length = word(12);
+        let document: serde_json::Value = serde_json::from_slice(&glb[20..20 + json_length]).unwrap();
+        assert_eq!(document["meshes"][0]["primitives"].as_array().unwrap().len(), 2);
+        assert_eq!(document["materials"][1]["name"], "pin");
+        // Positions in mm: the 2.54 mm vertex is written back as 2.54
+        let max = document["accessors"][0]["max"][0].as_f64().unwrap();
+        assert!((max - 2.54).abs() < 1e-6);
+
+        let bin_length = word(20 + json_length);
+        assert_eq!(bin_length, document["buffers"][0]["byteLength"].as_u64().unwrap() as usize);
+    }
+
+    #[test]
+    fn test_faces_with_invalid_indices_are_skipped() {
+        let vertices = vec![(0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.0)];
+        let faces = vec![vec![0, 1, 5], vec![-1, 0, 1], vec![2, 1, 0]];
+        let (new_vertices, new_faces) = ModelExporter::new().optimize_vertices(&vertices, &faces);
+
+        assert_eq!(new_faces, vec![vec![0, 1, 2]]);
+        assert_eq!(new_vertices, vec![(0.0, 1.0, 0.0), (1.0, 0.0, 0.0), (0.0, 0.0, 0.0)]);
+    }
+}
@@ -0,0 +1,251 @@
+use crate::kicad::footprint::{KiFootprint, PadChamfer, PadCorner, PadShape, PadType, PX_PER_MM};
+use std::str::FromStr;
+
+/// IPC-7351 corner radius: 25% of the smaller pad side, at most 0.25 mm
+const IPC_RADIUS_RATIO: f64 = 0.25;
+const IPC_RADIUS_MAX_MM: f64 = 0.25;
+
+/// Smallest tolerance used when matching polygon vertices (in mm)
+const POLYGON_TOLERANCE_MM: f64 = 0.005;
+
+/// Edge midpoints of a rounded corner stay this close to the radius; a single
+/// straight cut (chamfer) falls well below it
+const ROUNDED_MIDPOINT_RATIO: f64 = 0.9;
+
+/// How rectangular SMD pads get rounded corners
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RoundRectPolicy {
+    /// Keep sharp corners
+    #[default]
+    None,
+    /// IPC-7351: radius of 25% of the smaller side, capped at 0.25 mm
+    Ipc,
+    /// Fixed radius ratio (radius / smaller side, 0 to 0.5)
+    Fixed(f64),
+}
+
+impl RoundRectPolicy {
+    /// Corner ratio for a pad of the given size (in px), None to keep sharp corners
+    pub fn ratio(&self, size_x: f64, size_y: f64) -> Option<f64> {
+        match self {
+            RoundRectPolicy::None => None,
+            RoundRectPolicy::Ipc => {
+                let min_side = size_x.min(size_y);
+                if min_side <= 0.0 {
+                    return None;
+                }
+                Some(IPC_RADIUS_RATIO.min(IPC_RADIUS_MAX_MM * PX_PER_MM / min_side))
+            }
+            RoundRectPolicy::Fixed(ratio) => Some(*ratio),
+        }
+    }
+}
+
+impl FromStr for RoundRectPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(RoundRectPolicy::None),
+            "ipc" => Ok(RoundRectPolicy::Ipc),
+            other => match other.parse::<f64>() {
+                Ok(ratio) if (0.0..=0.5).contains(&ratio) => Ok(RoundRectPolicy::Fixed(ratio)),
+                _ => Err(format!("expected 'none', 'ipc' or a ratio between 0 and 0.5, got '{}'", s)),
+            },
+        }
+    }
+}
+
+/// Convert rectangular SMD pads to roundrect pads according to the policy
+pub fn apply_roundrect_policy(footprint: &mut KiFootprint, policy: RoundRectPolicy) {
+    for pad in &mut footprint.pads {
+        if pad.pad_type != PadType::Smd || pad.shape != PadShape::Rect {
+            continue;
+        }
+
+        if let Some(ratio) = policy.ratio(pad.size_x, pad.size_y)
+            && ratio > 0.0
+        {
+            pad.shape = PadShape::RoundRect;
+            pad.roundrect_ratio = ratio;
+        }
+    }
+}
+
+/// Native pad shape recognized from a polygon outline (coordinates in px)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolygonPadShape {
+    pub center: (f64, f64),
+    pub size: (f64, f64),
+    pub shape: PadShape,
+    pub roundrect_ratio: f64,
+    pub chamfer: Option<PadChamfer>,
+}
+
+/// Recognize axis-aligned plain, rounded and chamfered rectangles among polygon pads.
+///
+/// Returns None for any other outline, which then has to stay a custom pad.
+pub fn detect_polygon_pad(points: &[(f64, f64)]) -> Option<PolygonPadShape> {
+    let mut points = points.to_vec();
+    if points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    if points.len() < 4 {
+        return None;
+    }
+
+    let min_x = points.iter().map(|p| p.0).fold(f64::MAX, f64::min);
+    let min_y = points.iter().map(|p| p.1).fold(f64::MAX, f64::min);
+    let max_x = points.iter().map(|p| p.0).fold(f64::MIN, f64::max);
+    let max_y = points.iter().map(|p| p.1).fold(f64::MIN, f64::max);
+    let (width, height) = (max_x - min_x, max_y - min_y);
+    let min_side = width.min(height);
+    if min_side <= 0.0 {
+        return None;
+    }
+
+    let tol = (POLYGON_TOLERANCE_MM * PX_PER_MM).max(min_side * 0.01);
+    let near = |a: f64, b: f64| (a - b).abs() <= tol;
+
+    // Size of the cut at each corner, measured along both adjacent edges
+    let corners = [
+        (PadCorner::TopLeft, (min_x, min_y)),
+        (PadCorner::TopRight, (max_x, min_y)),
+        (PadCorner::BottomRight, (max_x, max_y)),
+        (PadCorner::BottomLeft, (min_x, max_y)),
+    ];
+    let mut cuts = Vec::with_capacity(corners.len());
+    for (corner, (cx, cy)) in corners {
+        let along_x = points.iter()
+            .filter(|p| near(p.1, cy))
+            .map(|p| (p.0 - cx).abs())
+            .fold(f64::MAX, f64::min);
+        let along_y = points.iter()
+            .filter(|p| near(p.0, cx))
+            .map(|p| (p.1 - cy).abs())
+            .fold(f64::MAX, f64::min);
+
+        if !near(along_x, along_y) {
+            return None;
+        }
+        cuts.push((corner, (along_x + along_y) / 2.0));
+    }
+
+    let on_outline = |p: &(f64, f64)| near(p.0, min_x) || near(p.0, max_x) || near(p.1, min_y) || near(p.1, max_y);
+    let mut detected = PolygonPadShape {
+        center: ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0),
+        size: (width, height),
+        shape: PadShape::Rect,
+        roundrect_ratio: 0.0,
+        chamfer: None,
+    };
+
+    let cut_corners: Vec<(PadCorner, f64)> = cuts.into_iter().filter(|(_, cut)| *cut > tol).collect();
+    if cut_corners.is_empty() {
+        return points.iter().all(on_outline).then_some(detected);
+    }
+
+    let cut = cut_corners[0].1;
+    if cut_corners.iter().any(|(_, c)| !near(*c, cut)) || cut > min_side / 2.0 + tol {
+        return None;
+    }
+
+    if points.iter().all(on_outline) {
+        // Only straight cuts between the edges: chamfered corners
+        detected.shape = PadShape::RoundRect;
+        detected.chamfer = Some(PadChamfer {
+            ratio: (cut / min_side).min(0.5),
+            corners: cut_corners.iter().map(|(corner, _)| *corner).collect(),
+        });
+        return Some(detected);
+    }
+
+    // Rounded corners: all four corners must be rounded with the same radius,
+    // with every vertex on the arc and the arcs made of more than one segment
+    if cut_corners.len() != corners.len() {
+        return None;
+    }
+
+    let half_inner_x = width / 2.0 - cut;
+    let half_inner_y = height / 2.0 - cut;
+    let distance = |p: (f64, f64)| {
+        let dx = ((p.0 - detected.center.0).abs() - half_inner_x).max(0.0);
+        let dy = ((p.1 - detected.center.1).abs() - half_inner_y).max(0.0);
+        dx.hypot(dy)
+    };
+
+    let vertices_on_arc = points.iter().all(|p| near(distance(*p), cut));
+    let segments_follow_arc = (0..points.len()).all(|idx| {
+        let (a, b) = (points[idx], points[(idx + 1) % points.len()]);
+        distance(((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0)) >= cut * ROUNDED_MIDPOINT_RATIO - tol
+    });
+
+    if !vertices_on_arc || !segments_follow_arc {
+        return None;
+    }
+
+    detected.shape = PadShape::RoundRect;
+    detected.roundrect_ratio = (cut / min_side).min(0.5);
+    Some(detected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rounded_rect(width: f64, height: f64, radius: f64, steps: usize) -> Vec<(f64, f64)> {
+        let centers = [
+            (width - radius, height - radius, 0.0),
+            (radius, height - radius, 90.0),
+            (radius, radius, 180.0),
+            (width - radius, radius, 270.0),
+        ];
+        let mut points = Vec::new();
+        for (cx, cy, start) in centers {
+            for step in 0..=steps {
+                let angle = (start + 90.0 * step as f64 / steps as f64).to_radians();
+                points.push((cx + radius * angle.cos(), cy + radius * angle.sin()));
+            }
+        }
+        points
+    }
+
+    #[test]
+    fn test_policy_parsing_and_ipc_ratio() {
+        assert_eq!("none".parse::<RoundRectPolicy>(), Ok(RoundRectPolicy::None));
+        assert_eq!("IPC".parse::<RoundRectPolicy>(), Ok(RoundRectPolicy::Ipc));
+        assert_eq!("0.2".parse::<RoundRectPolicy>(), Ok(RoundRectPolicy::Fixed(0.2)));
+        assert!("0.8".parse::<RoundRectPolicy>().is_err());
+
+        // 0.5 mm wide pad: 25% of the smaller side
+        let small = 0.5 * PX_PER_MM;
+        assert!((RoundRectPolicy::Ipc.ratio(small, 2.0 * small).unwrap() - 0.25).abs() < 1e-9);
+        // 2 mm wide pad: radius capped at 0.25 mm
+        let large = 2.0 * PX_PER_MM;
+        assert!((RoundRectPolicy::Ipc.ratio(large, large).unwrap() - 0.125).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_detect_rounded_polygon() {
+        let detected = detect_polygon_pad(&rounded_rect(10.0, 4.0, 1.0, 4)).unwrap();
+        assert_eq!(detected.shape, PadShape::RoundRect);
+        assert_eq!(detected.center, (5.0, 2.0));
+        assert!((detected.roundrect_ratio - 0.25).abs() < 1e-9);
+        assert!(detected.chamfer.is_none());
+    }
+
+    #[test]
+    fn test_detect_chamfered_and_plain_polygons() {
+        let chamfered = [(1.0, 0.0), (10.0, 0.0), (10.0, 4.0), (0.0, 4.0), (0.0, 1.0)];
+        let detected = detect_polygon_pad(&chamfered).unwrap();
+        let chamfer = detected.chamfer.unwrap();
+        assert_eq!(chamfer.corners, vec![PadCorner::TopLeft]);
+        assert!((chamfer.ratio - 0.25).abs() < 1e-9);
+
+        let plain = [(0.0, 0.0), (10.0, 0.0), (10.0, 4.0), (0.0, 4.0), (0.0, 0.0)];
+        assert_eq!(detect_polygon_pad(&plain).unwrap().shape, PadShape::Rect);
+
+        let l_shape = [(0.0, 0.0), (10.0, 0.0), (10.0, 4.0), (5.0, 4.0), (5.0, 8.0), (0.0, 8.0)];
+        assert!(detect_polygon_pad(&l_shape).is_none());
+    }
+}
@@ -0,0 +1,179 @@
+use serde::Deserialize;
+use crate::kicad::footprint::{KiFootprint, KiPad, PadShape, PadType, PX_PER_MM};
+
+/// Target aperture pitch when the grid size is chosen automatically (in mm)
+const AUTO_APERTURE_PITCH_MM: f64 = 1.5;
+
+/// Corner rounding of the generated paste apertures
+const APERTURE_ROUNDRECT_RATIO: f64 = 0.25;
+
+/// Paste windowing of large exposed pads
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct PasteGrid {
+    /// Pads with both sides at least this large (in mm) count as exposed pads
+    pub min_size: f64,
+    /// Aperture rows and columns; 0 picks a count from the pad size
+    pub rows: usize,
+    pub columns: usize,
+    /// Fraction of the pad area covered by paste
+    pub coverage: f64,
+}
+
+impl Default for PasteGrid {
+    fn default() -> Self {
+        Self {
+            min_size: 2.0,
+            rows: 0,
+            columns: 0,
+            coverage: 0.5,
+        }
+    }
+}
+
+/// Replace the full paste opening of exposed pads with a grid of smaller apertures.
+///
+/// Only footprints with more than two pads are considered, so large two-terminal
+/// parts (inductors, power resistors) keep their full paste. Returns the number
+/// of pads that were windowed.
+pub fn window_exposed_pads(footprint: &mut KiFootprint, grid: &PasteGrid) -> usize {
+    if footprint.pads.len() <= 2 || grid.coverage <= 0.0 || grid.coverage >= 1.0 {
+        return 0;
+    }
+
+    let mut apertures = Vec::new();
+    let mut windowed = 0;
+
+    for pad in &mut footprint.pads {
+        if !is_exposed_pad(pad, grid) {
+            continue;
+        }
+        let Some(paste_layer) = pad.layers.iter()
+            .find(|layer| *layer == "F.Paste" || *layer == "B.Paste")
+            .cloned()
+        else {
+            continue;
+        };
+
+        // Pad extents in board orientation (rotation is a multiple of 90 degrees)
+        let bbox = pad.bbox();
+        let (width, height) = (bbox.width(), bbox.height());
+        let columns = grid_count(grid.columns, width);
+        let rows = grid_count(grid.rows, height);
+
+        // Each aperture covers `coverage` of its cell, centered in the cell
+        let scale = grid.coverage.sqrt();
+        let (cell_x, cell_y) = (width / columns as f64, height / rows as f64);
+        for row in 0..rows {
+            for column in 0..columns {
+                apertures.push(KiPad {
+                    shape: PadShape::RoundRect,
+                    pos_x: bbox.min_x + cell_x * (column as f64 + 0.5),
+                    pos_y: bbox.min_y + cell_y * (row as f64 + 0.5),
+                    size_x: cell_x * scale,
+                    size_y: cell_y * scale,
+                    layers: vec![paste_layer.clone()],
+                    roundrect_ratio: APERTURE_ROUNDRECT_RATIO,
+                    ..KiPad::default()
+                });
+            }
+        }
+
+        pad.layers.retain(|layer| *layer != paste_layer);
+        windowed += 1;
+    }
+
+    footprint.pads.extend(apertures);
+    windowed
+}
+
+fn is_exposed_pad(pad: &KiPad, grid: &PasteGrid) -> bool {
+    let min_size = grid.min_size * PX_PER_MM;
+    pad.pad_type == PadType::Smd
+        && matches!(pad.shape, PadShape::Rect | PadShape::RoundRect)
+        && pad.chamfer.is_none()
+        && pad.rotation.rem_euclid(90.0).abs() < 1e-6
+        && pad.size_x >= min_size
+        && pad.size_y >= min_size
+}
+
+/// Configured aperture count, or one aperture per ~1.5 mm (at least 2)
+fn grid_count(configured: usize, size: f64) -> usize {
+    if configured > 0 {
+        return configured;
+    }
+    // Tolerance for rounding in the extents of rotated pads
+    ((size / PX_PER_MM / AUTO_APERTURE_PITCH_MM - 1e-6).ceil() as usize).max(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pad(number: &str, size_mm: f64) -> KiPad {
+        KiPad {
+            number: number.to_string(),
+            size_x: size_mm * PX_PER_MM,
+            size_y: size_mm * PX_PER_MM,
+            layers: vec!["F.Cu".to_string(), "F.Paste".to_string(), "F.Mask".to_string()],
+            ..KiPad::default()
+        }
+    }
+
+    fn footprint(pads: Vec<KiPad>) -> KiFootprint {
+        KiFootprint { pads, ..KiFootprint::default() }
+    }
+
+    #[test]
+    fn test_window_exposed_pad() {
+        let mut fp = footprint(vec![pad("1", 0.5), pad("2", 0.5), pad("3", 0.5), pad("9", 3.0)]);
+        let grid = PasteGrid { coverage: 0.64, ..PasteGrid::default() };
+
+        assert_eq!(window_exposed_pads(&mut fp, &grid), 1);
+        assert!(!fp.pads[3].layers.contains(&"F.Paste".to_string()));
+        assert!(fp.pads[0].layers.contains(&"F.Paste".to_string()));
+
+        // 3 mm pad: 2 x 2 apertures of 1.2 mm (80% of a 1.5 mm cell)
+        let apertures = &fp.pads[4..];
+        assert_eq!(apertures.len(), 4);
+        assert!((apertures[0].size_x / PX_PER_MM - 1.2).abs() < 1e-9);
+        assert!((apertures[0].pos_x / PX_PER_MM + 0.75).abs() < 1e-9);
+        assert_eq!(apertures[0].layers, vec!["F.Paste".to_string()]);
+    }
+
+    #[test]
+    fn test_two_pad_parts_keep_full_paste() {
+        let mut fp = footprint(vec![pad("1", 3.0), pad("2", 3.0)]);
+        assert_eq!(window_exposed_pads(&mut fp, &PasteGrid::default()), 0);
+        assert_eq!(fp.pads.len(), 2);
+    }
+
+    #[test]
+    fn test_rotated_exposed_pad() {
+        let mut exposed = pad("9", 3.0);
+        exposed.size_y = 4.0 * PX_PER_MM;
+        exposed.rotation = 90.0;
+        let mut fp = footprint(vec![pad("1", 0.5), pad("2", 0.5), pad("3", 0.5), exposed]);
+
+        // Apertures follow the rotated extents: 4 mm wide, 3 mm high
+        assert_eq!(window_exposed_pads(&mut fp, &PasteGrid::default()), 1);
+        let apertures = &fp.pads[4..];
+        assert_eq!(apertures.len(), 3 * 2);
+        assert!(apertures.iter().all(|a| a.rotation == 0.0));
+        assert!((apertures[0].pos_x / PX_PER_MM + 4.0 / 3.0).abs() < 1e-9);
+        assert!((apertures[0].pos_y / PX_PER_MM + 0.75).abs() < 1e-9);
+
+        // Pads at odd angles keep their full paste
+        let mut skewed = pad("9", 3.0);
+        skewed.rotation = 45.0;
+        let mut fp = footprint(vec![pad("1", 0.5), pad("2", 0.5), pad("3", 0.5), skewed]);
+        assert_eq!(window_exposed_pads(&mut fp, &PasteGrid::default()), 0);
+    }
+
+    #[test]
+    fn test_empty_footprint() {
+        let mut fp = footprint(Vec::new());
+        assert_eq!(window_exposed_pads(&mut fp, &PasteGrid::default()), 0);
+        assert!(fp.pads.is_empty());
+    }
+}
@@ -0,0 +1,453 @@
+use crate::converter::Converter;
+use crate::kicad::footprint::{KiArc, KiFootprint, KiLine, KiPad, PadShape, PX_PER_MM};
+use std::f64::consts::PI;
+
+/// Silk segments shorter than this after clipping are dropped (in mm)
+const MIN_SEGMENT_MM: f64 = 0.05;
+
+/// Angular step used when arcs and circles have to be split into segments
+const TESSELLATION_STEP_DEG: f64 = 5.0;
+
+/// Summary of a silkscreen clipping pass
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SilkClipReport {
+    /// Total silkscreen length removed (in mm)
+    pub removed_length: f64,
+    /// Silk items that were shortened, split or removed
+    pub clipped_items: usize,
+}
+
+/// Copper/mask keep-out area of a pad, in the pad frame
+enum Obstacle {
+    Circle { center: (f64, f64), radius: f64 },
+    Rect { center: (f64, f64), half: (f64, f64), rotation: f64 },
+}
+
+impl Obstacle {
+    fn from_pad(pad: &KiPad, margin: f64) -> Self {
+        let center = (pad.pos_x, pad.pos_y);
+        if pad.shape == PadShape::Circle {
+            return Obstacle::Circle { center, radius: pad.size_x / 2.0 + margin };
+        }
+
+        // Custom pads use the extents of their polygon
+        if pad.custom.is_some() {
+            let bbox = pad.bbox();
+            return Obstacle::Rect {
+                center: ((bbox.min_x + bbox.max_x) / 2.0, (bbox.min_y + bbox.max_y) / 2.0),
+                half: (bbox.width() / 2.0 + margin, bbox.height() / 2.0 + margin),
+                rotation: 0.0,
+            };
+        }
+
+        Obstacle::Rect {
+            center,
+            half: (pad.size_x / 2.0 + margin, pad.size_y / 2.0 + margin),
+            rotation: pad.rotation,
+        }
+    }
+
+    /// Parameter interval of the segment a-b that lies inside the obstacle
+    fn clip(&self, a: (f64, f64), b: (f64, f64)) -> Option<(f64, f64)> {
+        match *self {
+            Obstacle::Circle { center, radius } => circle_interval(a, b, center, radius),
+            Obstacle::Rect { center, half, rotation } => {
+                // Move the segment into the pad frame, where the pad is axis-aligned
+                let (sin, cos) = rotation.to_radians().sin_cos();
+                let local = |p: (f64, f64)| {
+                    let (dx, dy) = (p.0 - center.0, p.1 - center.1);
+                    (dx * cos - dy * sin, dx * sin + dy * cos)
+                };
+                liang_barsky(local(a), local(b), half)
+            }
+        }
+    }
+}
+
+/// Remove silkscreen that comes within `clearance` mm of pad copper or mask openings.
+///
+/// Lines are shortened or split; arcs and unfilled circles that touch a pad are
+/// replaced by the remaining line segments. Filled circles (pin 1 dots) are kept.
+pub fn clip_silkscreen(footprint: &mut KiFootprint, clearance: f64, converter: &Converter) -> SilkClipReport {
+    let mut report = SilkClipReport::default();
+
+    let footprint_mask = footprint.settings.mask_margin.unwrap_or(0.0);
+
+    // Obstacles per silk layer: copper and mask openings (grown by the mask margin),
+    // including half the silk stroke width
+    let obstacles = |layer: &str, width: f64| -> Vec<Obstacle> {
+        let (copper, mask) = if layer == "B.SilkS" { ("B.Cu", "B.Mask") } else { ("F.Cu", "F.Mask") };
+        footprint.pads.iter()
+            .filter_map(|pad| {
+                let on_copper = pad.layers.iter().any(|l| l == copper || l == "*.Cu");
+                let on_mask = pad.layers.iter().any(|l| l == mask || l == "*.Mask");
+                let margin = match (on_copper, on_mask) {
+                    (_, true) => pad.settings.mask_margin.unwrap_or(footprint_mask).max(0.0),
+                    (true, false) => 0.0,
+                    (false, false) => return None,
+                };
+                Some(Obstacle::from_pad(pad, (clearance + margin) * PX_PER_MM + width / 2.0))
+            })
+            .collect()
+    };
+    let is_silk = |layer: &str| layer == "F.SilkS" || layer == "B.SilkS";
+
+    let mut lines = Vec::with_capacity(footprint.lines.len());
+    for line in &footprint.lines {
+        if !is_silk(&line.layer) {
+            lines.push(line.clone());
+            continue;
+        }
+
+        let keep = clip_segment(line, &obstacles(&line.layer, line.width), &mut report);
+        if keep.len() != 1 || keep[0] != ((line.start_x, line.start_y), (line.end_x, line.end_y)) {
+            report.clipped_items += 1;
+        }
+        lines.extend(keep.into_iter().map(|(start, end)| with_points(line, start, end)));
+    }
+
+    let mut arcs = Vec::with_capacity(footprint.arcs.len());
+    for arc in &footprint.arcs {
+        let obstacles = obstacles(&arc.layer, arc.width);
+        let points = arc_points(arc, converter);
+        if !is_silk(&arc.layer) || !touches(&points, &obstacles) {
+            arcs.push(arc.clone());
+            continue;
+        }
+
+        report.clipped_items += 1;
+        let template = KiLine {
+            start_x: 0.0,
+            start_y: 0.0,
+            end_x: 0.0,
+            end_y: 0.0,
+            width: arc.width,
+            layer: arc.layer.clone(),
+        };
+        lines.extend(clip_polyline(&points, &template, &obstacles, &mut report));
+    }
+
+    let mut circles = Vec::with_capacity(footprint.circles.len());
+    for circle in &footprint.circles {
+        let obstacles = obstacles(&circle.layer, circle.width);
+        let radius = (circle.end_x - circle.center_x).hypot(circle.end_y - circle.center_y);
+        let points = circle_points((circle.center_x, circle.center_y), radius);
+        if !is_silk(&circle.layer) || circle.fill || !touches(&points, &obstacles) {
+            circles.push(circle.clone());
+            continue;
+        }
+
+        report.clipped_items += 1;
+
+        let template = KiLine {
+            start_x: 0.0,
+            start_y: 0.0,
+            end_x: 0.0,
+            end_y: 0.0,
+            width: circle.width,
+            layer: circle.layer.clone(),
+        };
+        lines.extend(clip_polyline(&points, &template, &obstacles, &mut report));
+    }
+
+    footprint.lines = lines;
+    footprint.arcs = arcs;
+    footprint.circles = circles;
+    report
+}
+
+type Segment = ((f64, f64), (f64, f64));
+
+/// Parts of a line outside all obstacles
+fn clip_segment(line: &KiLine, obstacles: &[Obstacle], report: &mut SilkClipReport) -> Vec<Segment> {
+    let (a, b) = ((line.start_x, line.start_y), (line.end_x, line.end_y));
+    let length = (b.0 - a.0).hypot(b.1 - a.1);
+    if length == 0.0 {
+        return vec![(a, b)];
+    }
+
+    let mut removed: Vec<(f64, f64)> = obstacles.iter().filter_map(|o| o.clip(a, b)).collect();
+    if removed.is_empty() {
+        return vec![(a, b)];
+    }
+
+    // Merge the removed intervals, then keep the gaps between them
+    removed.sort_by(|x, y| x.0.total_cmp(&y.0));
+    let mut merged: Vec<(f64, f64)> = Vec::new();
+    for (t0, t1) in removed {
+        match merged.last_mut() {
+            Some(last) if t0 <= last.1 => last.1 = last.1.max(t1),
+            _ => merged.push((t0, t1)),
+        }
+    }
+
+    let at = |t: f64| (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+    let min_t = MIN_SEGMENT_MM * PX_PER_MM / length;
+    let mut kept = Vec::new();
+    let mut cursor = 0.0;
+    for (t0, t1) in merged.into_iter().chain(std::iter::once((1.0, 1.0))) {
+        if t0 - cursor >= min_t {
+            kept.push((cursor, t0));
+        }
+        cursor = cursor.max(t1);
+    }
+
+    let kept_t: f64 = kept.iter().map(|(t0, t1)| t1 - t0).sum();
+    report.removed_length += (1.0 - kept_t) * length / PX_PER_MM;
+
+    kept.into_iter().map(|(t0, t1)| (at(t0), at(t1))).collect()
+}
+
+fn clip_polyline(points: &[(f64, f64)], template: &KiLine, obstacles: &[Obstacle], report: &mut SilkClipReport) -> Vec<KiLine> {
+    points.windows(2)
+        .flat_map(|pair| {
+            let line = with_points(template, pair[0], pair[1]);
+            clip_segment(&line, obstacles, report)
+                .into_iter()
+                .map(|(start, end)| with_points(template, start, end))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn touches(points: &[(f64, f64)], obstacles: &[Obstacle]) -> bool {
+    points.windows(2).any(|pair| obstacles.iter().any(|o| o.clip(pair[0], pair[1]).is_some()))
+}
+
+fn with_points(line: &KiLine, start: (f64, f64), end: (f64, f64)) -> KiLine {
+    KiLine {
+        start_x: start.0,
+        start_y: start.1,
+        end_x: end.0,
+        end_y: end.1,
+        width: line.width,
+        layer: line.layer.clone(),
+    }
+}
+
+/// Points along an arc through start, mid and end
+fn arc_points(arc: &KiArc, converter: &Converter) -> Vec<(f64, f64)> {
+    let (a, m, b) = ((arc.start_x, arc.start_y), (arc.mid_x, arc.mid_y), (arc.end_x, arc.end_y));
+    let Some((cx, cy, radius)) = converter.arc_center(a, m, b) else {
+        return vec![a, b];
+    };
+
+    let angle = |p: (f64, f64)| (p.1 - cy).atan2(p.0 - cx);
+    let (start, mid, end) = (angle(a), angle(m), angle(b));
+
+    // Sweep from start to end in the direction that passes through mid
+    let ccw = (end - start).rem_euclid(2.0 * PI);
+    let sweep = if (mid - start).rem_euclid(2.0 * PI) <= ccw { ccw } else { ccw - 2.0 * PI };
+
+    let steps = ((sweep.abs().to_degrees() / TESSELLATION_STEP_DEG).ceil() as usize).max(2);
+    (0..=steps)
+        .map(|step| {
+            let theta = start + sweep * step as f64 / steps as f64;
+            (cx + radius * theta.cos(), cy + radius * theta.sin())
+        })
+        .collect()
+}
+
+fn circle_points(center: (f64, f64), radius: f64) -> Vec<(f64, f64)> {
+    let steps = (360.0 / TESSELLATION_STEP_DEG) as usize;
+    (0..=steps)
+        .map(|step| {
+            let theta = 2.0 * PI * step as f64 / steps as f64;
+            (center.0 + radius * theta.cos(), center.1 + radius * theta.sin())
+        })
+        .collect()
+}
+
+/// Liang–Barsky clipping of segment a-b against the box [-half, half]
+fn liang_barsky(a: (f64, f64), b: (f64, f64), half: (f64, f64)) -> Option<(f64, f64)> {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let checks = [
+        (-dx, a.0 + half.0),
+        (dx, half.0 - a.0),
+        (-dy, a.1 + half.1),
+        (dy, half.1 - a.1),
+    ];
+
+    let (mut t0, mut t1) = (0.0f64, 1.0f64);
+    for (p, q) in checks {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+            continue;
+        }
+        let r = q / p;
+        if p < 0.0 {
+            t0 = t0.max(r);
+        } else {
+            t1 = t1.min(r);
+        }
+    }
+
+    (t0 < t1).then_some((t0, t1))
+}
+
+/// Parameter interval of segment a-b inside a circle
+fn circle_interval(a: (f64, f64), b: (f64, f64), center: (f64, f64), radius: f64) -> Option<(f64, f64)> {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let (fx, fy) = (a.0 - center.0, a.1 - center.1);
+    let qa = dx * dx + dy * dy;
+    if qa == 0.0 {
+        return None;
+    }
+    let qb = 2.0 * (fx * dx + fy * dy);
+    let qc = fx * fx + fy * fy - radius * radius;
+
+    let discriminant = qb * qb - 4.0 * qa * qc;
+    if discriminant <= 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    let t0 = ((-qb - root) / (2.0 * qa)).max(0.0);
+    let t1 = ((-qb + root) / (2.0 * qa)).min(1.0);
+    (t0 < t1).then_some((t0, t1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::KicadVersion;
+    use crate::kicad::footprint::*;
+
+    fn converter() -> Converter {
+        Converter::new(KicadVersion::V6)
+    }
+
+    fn footprint(pad_shape: PadShape, lines: Vec<KiLine>) -> KiFootprint {
+        let pad = KiPad {
+            number: "1".to_string(),
+            shape: pad_shape,
+            size_x: 1.0 * PX_PER_MM,
+            size_y: 1.0 * PX_PER_MM,
+            layers: vec!["F.Cu".to_string(), "F.Mask".to_string()],
+            ..KiPad::default()
+        };
+        KiFootprint { pads: vec![pad], lines, ..KiFootprint::default() }
+    }
+
+    fn silk(start: (f64, f64), end: (f64, f64)) -> KiLine {
+        KiLine {
+            start_x: start.0 * PX_PER_MM,
+            start_y: start.1 * PX_PER_MM,
+            end_x: end.0 * PX_PER_MM,
+            end_y: end.1 * PX_PER_MM,
+            width: 0.0,
+            layer: "F.SilkS".to_string(),
+        }
+    }
+
+    fn silk_circle(center: (f64, f64), radius: f64) -> KiCircle {
+        KiCircle {
+            center_x: center.0 * PX_PER_MM,
+            center_y: center.1 * PX_PER_MM,
+            end_x: (center.0 + radius) * PX_PER_MM,
+            end_y: center.1 * PX_PER_MM,
+            width: 0.0,
+            layer: "F.SilkS".to_string(),
+            fill: false,
+        }
+    }
+
+    #[test]
+    fn test_line_split_around_pad() {
+        let mut fp = footprint(PadShape::Rect, vec![silk((-3.0, 0.0), (3.0, 0.0)), silk((-3.0, 2.0), (3.0, 2.0))]);
+        let report = clip_silkscreen(&mut fp, 0.2, &converter());
+
+        // The line through the pad loses 1 mm of pad plus 2 x 0.2 mm clearance
+        assert_eq!(report.clipped_items, 1);
+        assert!((report.removed_length - 1.4).abs() < 1e-9);
+        assert_eq!(fp.lines.len(), 3);
+        assert!((fp.lines[0].end_x / PX_PER_MM + 0.7).abs() < 1e-9);
+        assert!((fp.lines[1].start_x / PX_PER_MM - 0.7).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_round_pad_and_silk_circle() {
+        let mut fp = footprint(PadShape::Circle, vec![silk((-3.0, 0.0), (3.0, 0.0))]);
+        fp.circles.push(silk_circle((0.0, 0.0), 0.6));
+        let report = clip_silkscreen(&mut fp, 0.0, &converter());
+
+        // The circle around the pad stays clear; the line loses the pad diameter
+        assert_eq!(fp.circles.len(), 1);
+        assert!((report.removed_length - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_filled_circle_is_kept() {
+        let mut fp = footprint(PadShape::Rect, Vec::new());
+        let mut dot = silk_circle((0.4, 0.0), 0.2);
+        dot.fill = true;
+        fp.circles.push(dot);
+        let report = clip_silkscreen(&mut fp, 0.2, &converter());
+
+        assert_eq!(fp.circles.len(), 1);
+        assert_eq!(report, SilkClipReport::default());
+    }
+
+    #[test]
+    fn test_mask_only_pad_and_mask_margin() {
+        let mut fp = footprint(PadShape::Rect, vec![silk((-3.0, 0.0), (3.0, 0.0))]);
+        fp.pads[0].layers = vec!["F.Mask".to_string()];
+        fp.settings.mask_margin = Some(0.1);
+        let report = clip_silkscreen(&mut fp, 0.2, &converter());
+
+        // 1 mm opening grown by 2 x (0.1 mm margin + 0.2 mm clearance)
+        assert_eq!(report.clipped_items, 1);
+        assert!((report.removed_length - 1.6).abs() < 1e-9);
+
+        // Copper without a mask opening ignores the mask margin
+        let mut fp = footprint(PadShape::Rect, vec![silk((-3.0, 0.0), (3.0, 0.0))]);
+        fp.pads[0].layers = vec!["F.Cu".to_string()];
+        fp.settings.mask_margin = Some(0.1);
+        let report = clip_silkscreen(&mut fp, 0.2, &converter());
+        assert!((report.removed_length - 1.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rotated_pad() {
+        let mut fp = footprint(PadShape::Rect, vec![silk((-3.0, 0.0), (3.0, 0.0))]);
+        fp.pads[0].rotation = 45.0;
+        let report = clip_silkscreen(&mut fp, 0.0, &converter());
+
+        // Along the diagonal of the rotated 1 mm square
+        assert!((report.removed_length - 2.0f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_arc_crossing_pad() {
+        let mut fp = footprint(PadShape::Rect, Vec::new());
+        fp.arcs.push(KiArc {
+            start_x: -2.0 * PX_PER_MM,
+            start_y: 0.0,
+            mid_x: 0.0,
+            mid_y: -2.0 * PX_PER_MM,
+            end_x: 2.0 * PX_PER_MM,
+            end_y: 0.0,
+            width: 0.0,
+            layer: "F.SilkS".to_string(),
+        });
+        fp.pads[0].pos_y = -2.0 * PX_PER_MM;
+        let report = clip_silkscreen(&mut fp, 0.0, &converter());
+
+        // The arc is replaced by segments on both sides of the pad
+        assert!(fp.arcs.is_empty());
+        assert_eq!(report.clipped_items, 1);
+        assert!(fp.lines.iter().all(|l| l.start_x.max(l.end_x) <= -0.4 * PX_PER_MM || l.start_x.min(l.end_x) >= 0.4 * PX_PER_MM));
+        assert!(fp.lines.iter().any(|l| l.start_x < 0.0) && fp.lines.iter().any(|l| l.start_x > 0.0));
+    }
+
+    #[test]
+    fn test_no_pads() {
+        let mut fp = footprint(PadShape::Rect, vec![silk((-3.0, 0.0), (3.0, 0.0))]);
+        fp.pads.clear();
+        let report = clip_silkscreen(&mut fp, 0.2, &converter());
+
+        assert_eq!(report, SilkClipReport::default());
+        assert_eq!(fp.lines.len(), 1);
+    }
+}
@@ -0,0 +1,380 @@
+use crate::error::{KicadError, Result};
+use std::collections::HashMap;
+use crate::kicad::footprint::{Ki3dModel, KiFootprint, PX_PER_MM};
+use crate::kicad::footprint_outline::footprint_bbox;
+
+/// Allowed distance between the model center and the footprint extents (in mm)
+const PLACEMENT_TOLERANCE_MM: f64 = 0.5;
+
+/// A model whose underside sits higher than this above the board is reported (in mm)
+const FLOATING_HEIGHT_MM: f64 = 1.0;
+
+/// Summary of a validated ISO-10303-21 (STEP) file
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepInfo {
+    /// First PRODUCT name in the data section
+    pub product_name: Option<String>,
+    /// Length unit of the geometry context, e.g. "mm", "inch", "m"
+    pub unit: String,
+    /// Bounding box of all 3D CARTESIAN_POINTs, converted to mm. Assembly placements
+    /// are not applied, so for multi-part files this is only approximate
+    pub bbox_min: (f64, f64, f64),
+    pub bbox_max: (f64, f64, f64),
+    /// The file places parts with assembly transforms, see `bbox_min`
+    pub approximate: bool,
+    /// Number of entity instances in the data section
+    pub entity_count: usize,
+}
+
+impl StepInfo {
+    pub fn size(&self) -> (f64, f64, f64) {
+        (
+            self.bbox_max.0 - self.bbox_min.0,
+            self.bbox_max.1 - self.bbox_min.1,
+            self.bbox_max.2 - self.bbox_min.2,
+        )
+    }
+}
+
+/// Validate a STEP file and extract its product name, units and bounding box.
+///
+/// The bounding box ignores assembly transforms (see `StepInfo::approximate`).
+/// Rejects data that is not an ISO-10303-21 exchange file (e.g. an HTML error
+/// page) and files that are truncated before the end of the data section.
+pub fn inspect_step(data: &[u8]) -> Result<StepInfo> {
+    let text = String::from_utf8_lossy(data);
+    let text = text.trim_start_matches('\u{feff}').trim();
+
+    if !text.starts_with("ISO-10303-21;") {
+        let start: String = text.chars().take(40).collect();
+        return Err(invalid(format!("not an ISO-10303-21 file (starts with {:?})", start)));
+    }
+    if !text.trim_end().ends_with("END-ISO-10303-21;") {
+        return Err(invalid("missing END-ISO-10303-21, file is truncated".to_string()));
+    }
+
+    let statements = split_statements(text);
+    let data_start = statements.iter().position(|s| s.as_str() == "DATA")
+        .ok_or_else(|| invalid("missing DATA section".to_string()))?;
+    if !statements.iter().take(data_start).any(|s| s.as_str() == "HEADER") {
+        return Err(invalid("missing HEADER section".to_string()));
+    }
+    let data_end = statements.iter().skip(data_start).position(|s| s.as_str() == "ENDSEC")
+        .map(|idx| data_start + idx)
+        .ok_or_else(|| invalid("DATA section is not closed".to_string()))?;
+
+    let mut info = StepInfo {
+        product_name: None,
+        unit: "mm".to_string(),
+        bbox_min: (f64::MAX, f64::MAX, f64::MAX),
+        bbox_max: (f64::MIN, f64::MIN, f64::MIN),
+        approximate: false,
+        entity_count: 0,
+    };
+    let mut entities: HashMap<&str, &str> = HashMap::new();
+    let mut unit_context = None;
+    let mut points = 0;
+
+    for statement in &statements[data_start + 1..data_end] {
+        let Some((id, entity)) = statement.split_once('=') else {
+            continue;
+        };
+        let entity = entity.trim();
+        entities.insert(id.trim(), entity);
+        info.entity_count += 1;
+
+        if let Some(args) = entity.strip_prefix("CARTESIAN_POINT") {
+            let coords = point_coordinates(args);
+            if let [x, y, z] = coords[..] {
+                info.bbox_min = (info.bbox_min.0.min(x), info.bbox_min.1.min(y), info.bbox_min.2.min(z));
+                info.bbox_max = (info.bbox_max.0.max(x), info.bbox_max.1.max(y), info.bbox_max.2.max(z));
+                points += 1;
+            }
+        } else if entity.starts_with("PRODUCT(") && info.product_name.is_none() {
+            info.product_name = first_string(entity);
+        } else if entity.contains("GLOBAL_UNIT_ASSIGNED_CONTEXT") && unit_context.is_none() {
+            unit_context = Some(entity);
+        } else if entity.starts_with("ITEM_DEFINED_TRANSFORMATION") {
+            info.approximate = true;
+        }
+    }
+
+    // The geometry context names its length unit; without one, a converted unit
+    // (inch) wins over the SI unit it is defined by
+    let context_unit = unit_context
+        .and_then(|context| context.split_once("GLOBAL_UNIT_ASSIGNED_CONTEXT"))
+        .and_then(|(_, units)| references(units).into_iter().find(|id| {
+            entities.get(id).is_some_and(|unit| unit.contains("LENGTH_UNIT"))
+        }))
+        .and_then(|id| entities.get(id).copied());
+    let unit = context_unit
+        .or_else(|| entities.values().copied().find(|e| e.contains("LENGTH_UNIT") && e.contains("CONVERSION_BASED_UNIT")))
+        .or_else(|| entities.values().copied().find(|e| e.contains("LENGTH_UNIT")));
+    let scale = match unit {
+        Some(unit) => {
+            let (name, scale) = length_unit(unit, &entities);
+            info.unit = name;
+            scale
+        }
+        None => 1.0,
+    };
+
+    if points == 0 {
+        return Err(invalid("no 3D geometry (CARTESIAN_POINT) in DATA section".to_string()));
+    }
+
+    let to_mm = |p: (f64, f64, f64)| (p.0 * scale, p.1 * scale, p.2 * scale);
+    info.bbox_min = to_mm(info.bbox_min);
+    info.bbox_max = to_mm(info.bbox_max);
+    Ok(info)
+}
+
+/// Check that a placed model lines up with the footprint; returns one message per problem.
+///
+/// Works on the bounding box of `inspect_step`, which is approximate for assemblies.
+pub fn check_model_placement(info: &StepInfo, model: &Ki3dModel, footprint: &KiFootprint) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    // Models tilted around x or y are left alone
+    if model.rotate.0 != 0.0 || model.rotate.1 != 0.0 {
+        return warnings;
+    }
+
+    // Rotation around z (counter-clockwise, y up) followed by the offset; KiCad's
+    // 3D y axis points up while footprint y points down
+    let (sin, cos) = model.rotate.2.to_radians().sin_cos();
+    let center = (
+        (info.bbox_min.0 + info.bbox_max.0) / 2.0 * model.scale.0,
+        (info.bbox_min.1 + info.bbox_max.1) / 2.0 * model.scale.1,
+    );
+    let placed_x = center.0 * cos - center.1 * sin + model.offset.0;
+    let placed_y = -(center.0 * sin + center.1 * cos + model.offset.1);
+
+    if let Some(bbox) = footprint_bbox(footprint) {
+        let bbox = bbox.expand(PLACEMENT_TOLERANCE_MM * PX_PER_MM);
+        let (x, y) = (placed_x * PX_PER_MM, placed_y * PX_PER_MM);
+        if x < bbox.min_x || x > bbox.max_x || y < bbox.min_y || y > bbox.max_y {
+            warnings.push(format!(
+                "3D model center ({:.2}, {:.2}) mm lies outside the footprint extents",
+                placed_x, placed_y
+            ));
+        }
+    }
+
+    let bottom = info.bbox_min.2 * model.scale.2 + model.offset.2;
+    let top = info.bbox_max.2 * model.scale.2 + model.offset.2;
+    if bottom > FLOATING_HEIGHT_MM {
+        warnings.push(format!("3D model floats {:.2} mm above the board", bottom));
+    } else if top <= 0.0 {
+        warnings.push("3D model lies completely below the board".to_string());
+    }
+
+    if info.approximate {
+        for warning in &mut warnings {
+            warning.push_str(" (approximate: assembly transforms are not applied)");
+        }
+    }
+    warnings
+}
+
+fn invalid(reason: String) -> crate::error::AppError {
+    KicadError::ModelExport(format!("invalid STEP file: {}", reason)).into()
+}
+
+/// Split into `;`-terminated statements, ignoring `;` inside strings and comments
+fn split_statements(text: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            current.push(c);
+            // '' is an escaped quote inside a string
+            if c == '\'' {
+                if chars.peek() == Some(&'\'') {
+                    current.push(chars.next().unwrap_or('\''));
+                } else {
+                    in_string = false;
+                }
+            }
+            continue;
+        }
+
+        match c {
+            '\'' => {
+                in_string = true;
+                current.push(c);
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                // Comment: skip to the closing */
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            ';' => {
+                statements.push(current.trim().to_string());
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+
+    statements
+}
+
+/// Numbers of the coordinate list in `('name',(x,y,z))`
+fn point_coordinates(args: &str) -> Vec<f64> {
+    let Some(start) = args.rfind('(') else {
+        return Vec::new();
+    };
+    let end = args[start..].find(')').map_or(args.len(), |idx| start + idx);
+
+    args[start + 1..end]
+        .split(',')
+        .filter_map(|value| value.trim().parse::<f64>().ok())
+        .collect()
+}
+
+/// First quoted string argument of an entity
+fn first_string(entity: &str) -> Option<String> {
+    let start = entity.find('\'')? + 1;
+    let end = start + entity[start..].find('\'')?;
+    let name = entity[start..end].trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Unit name and scale to mm of a LENGTH_UNIT entity
+fn length_unit(entity: &str, entities: &HashMap<&str, &str>) -> (String, f64) {
+    let upper = entity.to_ascii_uppercase();
+
+    // CONVERSION_BASED_UNIT('INCH',#7) with #7=LENGTH_MEASURE_WITH_UNIT(LENGTH_MEASURE(25.4),#8)
+    if let Some((_, rest)) = upper.split_once("CONVERSION_BASED_UNIT(") {
+        let name = first_string(rest).unwrap_or_default().to_ascii_lowercase();
+        let factor = references(rest).first()
+            .and_then(|id| entities.get(id))
+            .and_then(|measure| {
+                let value = measure.split_once("LENGTH_MEASURE(")?.1.split(')').next()?.trim().parse::<f64>().ok()?;
+                let base = references(measure).into_iter().next().and_then(|id| entities.get(id))?;
+                // The base is an SI unit, so there is no further conversion to follow
+                Some(value * length_unit(base, &HashMap::new()).1)
+            });
+        let factor = factor.or(match name.as_str() {
+            "inch" => Some(25.4),
+            "foot" => Some(304.8),
+            _ => None,
+        });
+        if let Some(factor) = factor {
+            return (name, factor);
+        }
+    }
+
+    match upper.split_once("SI_UNIT(").map(|(_, rest)| rest) {
+        Some(rest) if rest.starts_with(".MILLI.") => ("mm".to_string(), 1.0),
+        Some(rest) if rest.starts_with(".CENTI.") => ("cm".to_string(), 10.0),
+        Some(rest) if rest.starts_with(".MICRO.") => ("um".to_string(), 0.001),
+        Some(rest) if rest.starts_with('$') => ("m".to_string(), 1000.0),
+        _ => ("mm".to_string(), 1.0),
+    }
+}
+
+/// Entity references (`#12`) in an argument list, in order
+fn references(args: &str) -> Vec<&str> {
+    let mut refs = Vec::new();
+    let mut rest = args;
+    while let Some(start) = rest.find('#') {
+        let len = rest[start + 1..].find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len() - start - 1);
+        if len > 0 {
+            refs.push(&rest[start..start + 1 + len]);
+        }
+        rest = &rest[start + 1 + len..];
+    }
+    refs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP: &str = "ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('body; with semicolon'),'2;1');
+FILE_NAME('SOT-23.step','2024-01-01T00:00:00',(''),(''),'','','');
+FILE_SCHEMA(('AUTOMOTIVE_DESIGN'));
+ENDSEC;
+DATA;
+#1=PRODUCT('SOT-23','SOT-23','',(#2));
+#3=CARTESIAN_POINT('',(-0.146,-0.059,0.));
+#4=CARTESIAN_POINT('',(0.146,0.059,0.043));
+#5=CARTESIAN_POINT('pcurve',(5.,5.));
+/* comment; with semicolon */
+#6=(LENGTH_UNIT()NAMED_UNIT(*)CONVERSION_BASED_UNIT('INCH',#7));
+#7=LENGTH_MEASURE_WITH_UNIT(LENGTH_MEASURE(25.4),#8);
+#8=(LENGTH_UNIT()NAMED_UNIT(*)SI_UNIT(.MILLI.,.METRE.));
+#9=(GEOMETRIC_REPRESENTATION_CONTEXT(3)GLOBAL_UNIT_ASSIGNED_CONTEXT((#6,#10))REPRESENTATION_CONTEXT('',''));
+#10=(NAMED_UNIT(*)PLANE_ANGLE_UNIT()SI_UNIT($,.RADIAN.));
+ENDSEC;
+END-ISO-10303-21;
+";
+
+    #[test]
+    fn test_inspect_step() {
+        let info = inspect_step(STEP.as_bytes()).unwrap();
+        assert_eq!(info.product_name.as_deref(), Some("SOT-23"));
+        assert_eq!(info.unit, "inch");
+        assert_eq!(info.entity_count, 9);
+        assert!(!info.approximate);
+
+        // Inch coordinates converted to mm; the 2D point is ignored
+        let size = info.size();
+        assert!((size.0 - 7.4168).abs() < 1e-9);
+        assert!((info.bbox_max.2 - 1.0922).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rejects_html_and_truncated_files() {
+        assert!(inspect_step(b"<!DOCTYPE html><html>Not found</html>").is_err());
+
+        let truncated = &STEP[..STEP.find("#4=").unwrap()];
+        assert!(inspect_step(truncated.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_unit_from_context() {
+        // The context uses the SI unit; the inch unit is only declared
+        let step = STEP.replace("GLOBAL_UNIT_ASSIGNED_CONTEXT((#6,#10))", "GLOBAL_UNIT_ASSIGNED_CONTEXT((#8,#10))");
+        let info = inspect_step(step.as_bytes()).unwrap();
+        assert_eq!(info.unit, "mm");
+        assert!((info.size().0 - 0.292).abs() < 1e-9);
+
+        // Conversion factor relative to an SI metre
+        let step = STEP
+            .replace("LENGTH_MEASURE(25.4)", "LENGTH_MEASURE(0.0254)")
+            .replace("SI_UNIT(.MILLI.,.METRE.)", "SI_UNIT($,.METRE.)");
+        let info = inspect_step(step.as_bytes()).unwrap();
+        assert!((info.size().0 - 7.4168).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_assembly_placement_is_approximate() {
+        let step = STEP.replace("#5=", "#11=ITEM_DEFINED_TRANSFORMATION('','',#12,#13);\n#5=");
+        let info = inspect_step(step.as_bytes()).unwrap();
+        assert!(info.approximate);
+
+        let model = Ki3dModel {
+            path: String::new(),
+            offset: (0.0, 0.0, 5.0),
+            scale: (1.0, 1.0, 1.0),
+            rotate: (0.0, 0.0, 0.0),
+        };
+        let warnings = check_model_placement(&info, &model, &KiFootprint::default());
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].ends_with("(approximate: assembly transforms are not applied)"));
+    }
+}
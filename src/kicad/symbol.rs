@@ -107,7 +107,7 @@ impl PinStyle {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct KiSymbol {
     pub name: String,
     pub reference: String,
@@ -122,6 +122,31 @@ pub struct KiSymbol {
     pub circles: Vec<KiCircle>,
     pub arcs: Vec<KiArc>,
     pub polylines: Vec<KiPolyline>,
+    pub beziers: Vec<KiBezier>,
+    pub texts: Vec<KiText>,
+    pub pin_layout: PinTextLayout,
+}
+
+/// Pin name/number text settings for a whole symbol (sizes and offset in mm)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PinTextLayout {
+    pub names_hidden: bool,
+    pub numbers_hidden: bool,
+    pub name_offset: Option<f64>,  // None keeps KiCad's default offset
+    pub name_size: f64,
+    pub number_size: f64,
+}
+
+impl Default for PinTextLayout {
+    fn default() -> Self {
+        Self {
+            names_hidden: false,
+            numbers_hidden: false,
+            name_offset: None,
+            name_size: 1.27,
+            number_size: 1.27,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -134,6 +159,57 @@ pub struct KiPin {
     pub pos_y: f64,
     pub rotation: i32,
     pub length: f64,
+    pub alternates: Vec<KiPinAlternate>,
+}
+
+/// Alternate pin function selectable in KiCad's pin alternate menu
+#[derive(Debug, Clone, PartialEq)]
+pub struct KiPinAlternate {
+    pub name: String,
+    pub pin_type: PinType,
+    pub style: PinStyle,
+}
+
+/// Pin names whose slash is part of a single signal name, not a function separator
+const SLASHED_SIGNAL_NAMES: &[&str] = &["I/O", "R/W", "R/~W", "~R/W", "D/C", "A/D", "N/C", "~CS/WR"];
+
+impl KiPin {
+    /// Split a multiplexed name like "PA9/USART1_TX/TIM1_CH2" into the primary
+    /// name "PA9" and alternates for the remaining functions. Signal names such as
+    /// "I/O" or "R/W" stay whole.
+    pub fn split_alternates(&mut self) {
+        if SLASHED_SIGNAL_NAMES.iter().any(|name| name.eq_ignore_ascii_case(self.name.trim())) {
+            return;
+        }
+
+        let parts: Vec<&str> = self.name.split('/')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .collect();
+
+        let Some((primary, rest)) = parts.split_first() else {
+            return;
+        };
+
+        let mut alternates: Vec<KiPinAlternate> = Vec::new();
+        for part in rest {
+            if part == primary || alternates.iter().any(|alternate| alternate.name == *part) {
+                continue;
+            }
+            alternates.push(KiPinAlternate {
+                name: part.to_string(),
+                pin_type: self.pin_type,
+                style: self.style,
+            });
+        }
+
+        if alternates.is_empty() {
+            return;
+        }
+
+        self.name = primary.to_string();
+        self.alternates = alternates;
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -170,5 +246,251 @@ pub struct KiArc {
 pub struct KiPolyline {
     pub points: Vec<(f64, f64)>,
     pub stroke_width: f64,
-    pub fill: bool,
+    pub fill: FillType,
+}
+
+/// Cubic bezier: start point, two control points, end point
+#[derive(Debug, Clone)]
+pub struct KiBezier {
+    pub points: [(f64, f64); 4],
+    pub stroke_width: f64,
+}
+
+/// Free text drawn in the symbol body
+#[derive(Debug, Clone)]
+pub struct KiText {
+    pub text: String,
+    pub pos_x: f64,
+    pub pos_y: f64,
+    pub rotation: i32,  // Degrees, counter-clockwise
+    pub font_size: f64,  // In EasyEDA px
+}
+
+/// Fill mode of a closed shape
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillType {
+    None,
+    /// Filled with the stroke colour (e.g. diode and transistor arrow heads)
+    Outline,
+    /// Filled with the body background colour
+    Background,
+}
+
+impl FillType {
+    pub fn to_kicad_v6(&self) -> &'static str {
+        match self {
+            FillType::None => "none",
+            FillType::Outline => "outline",
+            FillType::Background => "background",
+        }
+    }
+
+    pub fn to_kicad_v5(&self) -> &'static str {
+        match self {
+            FillType::None => "N",
+            FillType::Outline => "F",
+            FillType::Background => "f",
+        }
+    }
+}
+
+impl KiSymbol {
+    /// Add an SVG path (M, L, C and Z commands) relative to the symbol origin;
+    /// straight runs become polylines and each C segment a bezier
+    pub fn push_path(&mut self, path_data: &str, origin: (f64, f64), stroke_width: f64, fill: FillType) {
+        let tokens: Vec<&str> = path_data.split_whitespace().collect();
+        let mut points: Vec<(f64, f64)> = Vec::new();
+        let mut subpath_start: Option<(f64, f64)> = None;
+        let mut i = 0;
+
+        // Parse a coordinate pair at tokens[i] (may be "x,y" or separate "x" "y"),
+        // returning the adjusted point and the number of tokens consumed
+        let read_point = |i: usize| -> Option<((f64, f64), usize)> {
+            let (x, y, consumed) = if let Some((x_str, y_str)) = tokens.get(i)?.split_once(',') {
+                (x_str.parse::<f64>().ok()?, y_str.parse::<f64>().ok()?, 1)
+            } else {
+                (tokens.get(i)?.parse::<f64>().ok()?, tokens.get(i + 1)?.parse::<f64>().ok()?, 2)
+            };
+            Some(((x - origin.0, origin.1 - y), consumed))
+        };
+
+        while i < tokens.len() {
+            let token = tokens[i];
+            match token {
+                "M" | "L" => {
+                    // Move or Line command, followed by x,y coordinates
+                    if let Some((point, consumed)) = read_point(i + 1) {
+                        if token == "M" {
+                            // A move starts a new subpath: flush the current run
+                            if points.len() >= 2 {
+                                self.polylines.push(KiPolyline {
+                                    points: std::mem::take(&mut points),
+                                    stroke_width,
+                                    fill,
+                                });
+                            }
+                            points.clear();
+                            subpath_start = Some(point);
+                        } else if subpath_start.is_none() {
+                            subpath_start = Some(point);
+                        }
+                        points.push(point);
+                        i += consumed;
+                    }
+                }
+                "C" => {
+                    // Cubic bezier: two control points and an end point
+                    let start = points.last().copied().or(subpath_start);
+                    let mut controls = Vec::with_capacity(3);
+                    let mut j = i + 1;
+                    while controls.len() < 3 {
+                        let Some((point, consumed)) = read_point(j) else { break };
+                        controls.push(point);
+                        j += consumed;
+                    }
+
+                    if let (Some(start), [c1, c2, end]) = (start, controls.as_slice()) {
+                        // Flush the straight segments drawn so far
+                        if points.len() >= 2 {
+                            self.polylines.push(KiPolyline {
+                                points: std::mem::take(&mut points),
+                                stroke_width,
+                                fill,
+                            });
+                        }
+                        self.beziers.push(KiBezier {
+                            points: [start, *c1, *c2, *end],
+                            stroke_width,
+                        });
+                        points = vec![*end];
+                        i = j - 1;
+                    }
+                }
+                "Z" | "z" => {
+                    // Close path: add line from current point back to start point
+                    if let Some(first_point) = subpath_start
+                        && points.last() != Some(&first_point)
+                    {
+                        points.push(first_point);
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+
+        if points.len() >= 2 {
+            self.polylines.push(KiPolyline { points, stroke_width, fill });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pin(name: &str) -> KiPin {
+        KiPin {
+            number: "1".to_string(),
+            name: name.to_string(),
+            pin_type: PinType::Bidirectional,
+            style: PinStyle::Line,
+            pos_x: 0.0,
+            pos_y: 0.0,
+            rotation: 0,
+            length: 10.0,
+            alternates: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_split_alternates() {
+        let mut p = pin("PA9/USART1_TX/TIM1_CH2");
+        p.split_alternates();
+        assert_eq!(p.name, "PA9");
+        let names: Vec<&str> = p.alternates.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["USART1_TX", "TIM1_CH2"]);
+        assert_eq!(p.alternates[0].pin_type, PinType::Bidirectional);
+    }
+
+    #[test]
+    fn test_split_alternates_plain_name() {
+        let mut p = pin("VCC");
+        p.split_alternates();
+        assert_eq!(p.name, "VCC");
+        assert!(p.alternates.is_empty());
+
+        let mut p = pin("/");
+        p.split_alternates();
+        assert_eq!(p.name, "/");
+        assert!(p.alternates.is_empty());
+
+        // Slashed signal names are not separate functions
+        for name in ["I/O", "R/W", "i/o", "~CS/WR"] {
+            let mut p = pin(name);
+            p.split_alternates();
+            assert_eq!(p.name, name);
+            assert!(p.alternates.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_split_alternates_short_functions() {
+        let mut p = pin("PB3/SWO/IO");
+        p.split_alternates();
+        assert_eq!(p.name, "PB3");
+        let names: Vec<&str> = p.alternates.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["SWO", "IO"]);
+
+        let mut p = pin("D0/RX");
+        p.split_alternates();
+        assert_eq!(p.name, "D0");
+        assert_eq!(p.alternates[0].name, "RX");
+    }
+
+    #[test]
+    fn test_split_alternates_removes_duplicates() {
+        let mut p = pin("PA10/USART1_RX/TIM1_CH3/USART1_RX/PA10");
+        p.split_alternates();
+        assert_eq!(p.name, "PA10");
+        let names: Vec<&str> = p.alternates.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["USART1_RX", "TIM1_CH3"]);
+    }
+
+    #[test]
+    fn test_push_path_with_curve() {
+        let mut symbol = KiSymbol::default();
+        symbol.push_path("M 10 20 L 15,20 C 15 25 20 25 20 20 L 20 10 Z", (10.0, 20.0), 1.0, FillType::None);
+
+        assert_eq!(symbol.beziers.len(), 1);
+        assert_eq!(symbol.beziers[0].points, [(5.0, 0.0), (5.0, -5.0), (10.0, -5.0), (10.0, 0.0)]);
+        let runs: Vec<&Vec<(f64, f64)>> = symbol.polylines.iter().map(|p| &p.points).collect();
+        assert_eq!(runs, vec![
+            &vec![(0.0, 0.0), (5.0, 0.0)],
+            &vec![(10.0, 0.0), (10.0, 10.0), (0.0, 0.0)],
+        ]);
+    }
+
+    #[test]
+    fn test_push_path_incomplete_curve() {
+        let mut symbol = KiSymbol::default();
+        symbol.push_path("M 0 0 L 10 0 C 10 5 5", (0.0, 0.0), 1.0, FillType::Outline);
+
+        assert!(symbol.beziers.is_empty());
+        assert_eq!(symbol.polylines.len(), 1);
+        assert_eq!(symbol.polylines[0].points, vec![(0.0, 0.0), (10.0, 0.0)]);
+        assert_eq!(symbol.polylines[0].fill, FillType::Outline);
+    }
+
+    #[test]
+    fn test_push_path_two_subpaths() {
+        let mut symbol = KiSymbol::default();
+        symbol.push_path("M 0 0 L 10 0 M 0 10 L 10 10 Z", (0.0, 0.0), 1.0, FillType::None);
+
+        let runs: Vec<&Vec<(f64, f64)>> = symbol.polylines.iter().map(|p| &p.points).collect();
+        assert_eq!(runs, vec![
+            &vec![(0.0, 0.0), (10.0, 0.0)],
+            &vec![(0.0, -10.0), (10.0, -10.0), (0.0, -10.0)],
+        ]);
+    }
 }
@@ -1,6 +1,6 @@
 use crate::cli::KicadVersion;
 use crate::converter::Converter;
-use crate::error::Result;
+use crate::error::{KicadError, Result};
 use crate::kicad::symbol::*;
 
 pub struct SymbolExporter {
@@ -18,7 +18,7 @@ impl SymbolExporter {
 
     pub fn export(&self, symbol: &KiSymbol) -> Result<String> {
         match self.version {
-            KicadVersion::V6 => self.export_v6(symbol),
+            KicadVersion::V6 | KicadVersion::V8 | KicadVersion::V9 => self.export_v6(symbol),
             KicadVersion::V5 => self.export_v5(symbol),
         }
     }
@@ -31,9 +31,86 @@ impl SymbolExporter {
 
         // Start symbol definition - match Python formatting
         output.push_str(&format!("  (symbol \"{}\"\n", symbol.name));
+
+        // Pin text visibility and offset
+        let layout = &symbol.pin_layout;
+        if layout.numbers_hidden {
+            output.push_str("    (pin_numbers hide)\n");
+        }
+        match (layout.name_offset, layout.names_hidden) {
+            (Some(offset), true) => output.push_str(&format!("    (pin_names (offset {:.3}) hide)\n", offset)),
+            (Some(offset), false) => output.push_str(&format!("    (pin_names (offset {:.3}))\n", offset)),
+            (None, true) => output.push_str("    (pin_names hide)\n"),
+            (None, false) => {}
+        }
+
         output.push_str("    (in_bom yes)\n");
         output.push_str("    (on_board yes)\n");
 
+        output.push_str(&self.format_properties_v6(symbol, y_high, y_low));
+
+        // Symbol graphics section (unit 0, convert 1) - contains body graphics
+        output.push_str(&format!("    (symbol \"{}_0_1\"\n", symbol.name));
+
+        // Rectangles
+        for rect in &symbol.rectangles {
+            output.push_str(&self.format_rectangle_v6(rect));
+        }
+
+        // Circles
+        for circle in &symbol.circles {
+            output.push_str(&self.format_circle_v6(circle));
+        }
+
+        // Arcs
+        for arc in &symbol.arcs {
+            output.push_str(&self.format_arc_v6(arc));
+        }
+
+        // Polylines
+        for polyline in &symbol.polylines {
+            output.push_str(&self.format_polyline_v6(polyline));
+        }
+
+        // Beziers
+        for bezier in &symbol.beziers {
+            output.push_str(&self.format_bezier_v6(bezier));
+        }
+
+        // Texts
+        for text in &symbol.texts {
+            output.push_str(&self.format_text_v6(text));
+        }
+
+        // Pins - in the same _0_1 section as graphics
+        for pin in &symbol.pins {
+            output.push_str(&self.format_pin_v6(pin, &symbol.pin_layout));
+        }
+
+        output.push_str("    )\n");
+        output.push_str("  )\n");
+
+        Ok(output)
+    }
+
+    /// Export a derived symbol that reuses the graphics and pins of `base_name`
+    pub fn export_derived(&self, symbol: &KiSymbol, base_name: &str) -> Result<String> {
+        if self.version == KicadVersion::V5 {
+            return Err(KicadError::SymbolExport("Derived symbols require KiCad v6 format or newer".to_string()).into());
+        }
+
+        let (y_high, y_low) = self.calculate_y_bounds(symbol);
+
+        let mut output = format!("  (symbol \"{}\" (extends \"{}\")\n", symbol.name, base_name);
+        output.push_str(&self.format_properties_v6(symbol, y_high, y_low));
+        output.push_str("  )\n");
+
+        Ok(output)
+    }
+
+    fn format_properties_v6(&self, symbol: &KiSymbol, y_high: f64, y_low: f64) -> String {
+        let mut output = String::new();
+
         // Properties with proper formatting
         const FIELD_OFFSET_START: f64 = 5.08;
         const FIELD_OFFSET_INCREMENT: f64 = 2.54;
@@ -124,38 +201,7 @@ impl SymbolExporter {
             output.push_str("    )\n");
         }
 
-        // Symbol graphics section (unit 0, convert 1) - contains body graphics
-        output.push_str(&format!("    (symbol \"{}_0_1\"\n", symbol.name));
-
-        // Rectangles
-        for rect in &symbol.rectangles {
-            output.push_str(&self.format_rectangle_v6(rect));
-        }
-
-        // Circles
-        for circle in &symbol.circles {
-            output.push_str(&self.format_circle_v6(circle));
-        }
-
-        // Arcs
-        for arc in &symbol.arcs {
-            output.push_str(&self.format_arc_v6(arc));
-        }
-
-        // Polylines
-        for polyline in &symbol.polylines {
-            output.push_str(&self.format_polyline_v6(polyline));
-        }
-
-        // Pins - in the same _0_1 section as graphics
-        for pin in &symbol.pins {
-            output.push_str(&self.format_pin_v6(pin));
-        }
-
-        output.push_str("    )\n");
-        output.push_str("  )\n");
-
-        Ok(output)
+        output
     }
 
     fn calculate_y_bounds(&self, symbol: &KiSymbol) -> (f64, f64) {
@@ -183,9 +229,15 @@ impl SymbolExporter {
         let mut output = String::new();
 
         // DEF name reference unused text_offset draw_pinnumber draw_pinname unit_count units_locked option_flag
+        let layout = &symbol.pin_layout;
+        let text_offset = layout.name_offset.map(|mm| self.mm_to_mil(mm)).unwrap_or(40);
         output.push_str(&format!(
-            "DEF {} {} 0 40 Y Y 1 F N\n",
-            symbol.name, symbol.reference
+            "DEF {} {} 0 {} {} {} 1 F N\n",
+            symbol.name,
+            symbol.reference,
+            text_offset,
+            if layout.numbers_hidden { 'N' } else { 'Y' },
+            if layout.names_hidden { 'N' } else { 'Y' }
         ));
 
         // F0 reference x y size orientation visibility hjustify vjustify/italic/bold
@@ -207,14 +259,29 @@ impl SymbolExporter {
             output.push_str(&self.format_circle_v5(circle));
         }
 
+        // Arcs
+        for arc in &symbol.arcs {
+            output.push_str(&self.format_arc_v5(arc));
+        }
+
         // Polylines
         for polyline in &symbol.polylines {
             output.push_str(&self.format_polyline_v5(polyline));
         }
 
+        // Beziers
+        for bezier in &symbol.beziers {
+            output.push_str(&self.format_bezier_v5(bezier));
+        }
+
+        // Texts
+        for text in &symbol.texts {
+            output.push_str(&self.format_text_v5(text));
+        }
+
         // Pins
         for pin in &symbol.pins {
-            output.push_str(&self.format_pin_v5(pin));
+            output.push_str(&self.format_pin_v5(pin, &symbol.pin_layout));
         }
 
         output.push_str("ENDDRAW\n");
@@ -223,7 +290,7 @@ impl SymbolExporter {
         Ok(output)
     }
 
-    fn format_pin_v6(&self, pin: &KiPin) -> String {
+    fn format_pin_v6(&self, pin: &KiPin, layout: &PinTextLayout) -> String {
         let x = self.converter.px_to_mm(pin.pos_x);
         let y = self.converter.px_to_mm(pin.pos_y);
         let length = self.converter.px_to_mm(pin.length);
@@ -231,8 +298,8 @@ impl SymbolExporter {
         // Convert pin rotation: (180 + orientation) % 360
         let orientation = (180 + pin.rotation) % 360;
 
-        format!(
-            "      (pin {} {}\n        (at {:.2} {:.2} {})\n        (length {:.2})\n        (name \"{}\" (effects (font (size 1.27 1.27))))\n        (number \"{}\" (effects (font (size 1.27 1.27))))\n      )\n",
+        let mut output = format!(
+            "      (pin {} {}\n        (at {:.2} {:.2} {})\n        (length {:.2})\n        (name \"{}\" (effects (font (size {:.3} {:.3}))))\n        (number \"{}\" (effects (font (size {:.3} {:.3}))))\n",
             pin.pin_type.to_kicad_v6(),
             pin.style.to_kicad_v6(),
             x,
@@ -240,11 +307,28 @@ impl SymbolExporter {
             orientation,
             length,
             pin.name,
-            pin.number
-        )
+            layout.name_size,
+            layout.name_size,
+            pin.number,
+            layout.number_size,
+            layout.number_size
+        );
+
+        // Alternate pin functions
+        for alternate in &pin.alternates {
+            output.push_str(&format!(
+                "        (alternate \"{}\" {} {})\n",
+                alternate.name,
+                alternate.pin_type.to_kicad_v6(),
+                alternate.style.to_kicad_v6()
+            ));
+        }
+
+        output.push_str("      )\n");
+        output
     }
 
-    fn format_pin_v5(&self, pin: &KiPin) -> String {
+    fn format_pin_v5(&self, pin: &KiPin, layout: &PinTextLayout) -> String {
         let x = self.converter.px_to_mil(pin.pos_x);
         let y = self.converter.px_to_mil(pin.pos_y);  // Don't flip, already handled
         let length = self.converter.px_to_mil(pin.length);
@@ -258,8 +342,8 @@ impl SymbolExporter {
             y,
             length,
             self.rotation_to_direction(pin.rotation),
-            50, // name size
-            50, // number size
+            self.mm_to_mil(layout.number_size),  // number size
+            self.mm_to_mil(layout.name_size),    // name size
             1,  // unit
             1,  // convert
             pin.pin_type.to_kicad_v5()
@@ -287,7 +371,8 @@ impl SymbolExporter {
         let x2 = self.converter.px_to_mil(rect.x2);
         let y2 = self.converter.px_to_mil(rect.y2);  // Don't flip, already handled
 
-        let fill = if rect.fill { "F" } else { "N" };
+        // Same fill as v6 output: body background
+        let fill = if rect.fill { FillType::Background } else { FillType::None }.to_kicad_v5();
 
         // S startx starty endx endy unit convert thickness fill
         format!("S {} {} {} {} 1 1 10 {}\n", x1, y1, x2, y2, fill)
@@ -343,11 +428,9 @@ impl SymbolExporter {
         }
 
         let width = self.converter.px_to_mm(polyline.stroke_width);
-        let fill = if polyline.fill { "background" } else { "none" };
-
         output.push_str("      )\n");
         output.push_str(&format!("      (stroke (width {:.4}) (type default))\n", width));
-        output.push_str(&format!("      (fill (type {}))\n", fill));
+        output.push_str(&format!("      (fill (type {}))\n", polyline.fill.to_kicad_v6()));
         output.push_str("    )\n");
 
         output
@@ -363,12 +446,119 @@ impl SymbolExporter {
             output.push_str(&format!(" {} {}", x, y));
         }
 
-        let fill = if polyline.fill { "F" } else { "N" };
-        output.push_str(&format!(" {}\n", fill));
+        output.push_str(&format!(" {}\n", polyline.fill.to_kicad_v5()));
+
+        output
+    }
+
+    fn format_arc_v5(&self, arc: &KiArc) -> String {
+        let start = (self.converter.px_to_mil(arc.start_x), self.converter.px_to_mil(arc.start_y));
+        let mid = (self.converter.px_to_mil(arc.mid_x), self.converter.px_to_mil(arc.mid_y));
+        let end = (self.converter.px_to_mil(arc.end_x), self.converter.px_to_mil(arc.end_y));
+
+        let as_f64 = |p: (i32, i32)| (p.0 as f64, p.1 as f64);
+        let Some((cx, cy, radius)) = self.converter.arc_center(as_f64(start), as_f64(mid), as_f64(end)) else {
+            // Collinear points: draw a straight segment instead
+            return format!("P 2 1 1 10 {} {} {} {} N\n", start.0, start.1, end.0, end.1);
+        };
+
+        // KiCad v5 draws arcs counter-clockwise from the first to the second angle,
+        // so swap the endpoints when the mid point lies on the clockwise side
+        let angle = |p: (i32, i32)| (p.1 as f64 - cy).atan2(p.0 as f64 - cx).to_degrees();
+        let (a_start, a_mid, a_end) = (angle(start), angle(mid), angle(end));
+        let sweep = (a_end - a_start).rem_euclid(360.0);
+        let (first, second, a_first, a_second) = if (a_mid - a_start).rem_euclid(360.0) <= sweep {
+            (start, end, a_start, a_end)
+        } else {
+            (end, start, a_end, a_start)
+        };
+
+        // A posx posy radius start_angle end_angle unit convert thickness fill startx starty endx endy
+        // (angles in tenths of a degree)
+        format!(
+            "A {} {} {} {} {} 1 1 10 N {} {} {} {}\n",
+            cx.round() as i32,
+            cy.round() as i32,
+            radius.round() as i32,
+            (a_first * 10.0).round() as i32,
+            (a_second * 10.0).round() as i32,
+            first.0,
+            first.1,
+            second.0,
+            second.1
+        )
+    }
+
+    fn format_bezier_v6(&self, bezier: &KiBezier) -> String {
+        let mut output = String::from("    (bezier\n      (pts\n");
+
+        for (x, y) in &bezier.points {
+            let x = self.converter.px_to_mm(*x);
+            let y = self.converter.px_to_mm(*y);  // Don't flip, already handled
+            output.push_str(&format!("        (xy {:.4} {:.4})\n", x, y));
+        }
+
+        let width = self.converter.px_to_mm(bezier.stroke_width);
+
+        output.push_str("      )\n");
+        output.push_str(&format!("      (stroke (width {:.4}) (type default))\n", width));
+        output.push_str("      (fill (type none))\n");
+        output.push_str("    )\n");
 
         output
     }
 
+    fn format_bezier_v5(&self, bezier: &KiBezier) -> String {
+        let mut output = format!("B {} 1 1 10", bezier.points.len());
+
+        for (x, y) in &bezier.points {
+            let x = self.converter.px_to_mil(*x);
+            let y = self.converter.px_to_mil(*y);  // Don't flip, already handled
+            output.push_str(&format!(" {} {}", x, y));
+        }
+
+        output.push_str(" N\n");
+
+        output
+    }
+
+    fn format_text_v6(&self, text: &KiText) -> String {
+        let x = self.converter.px_to_mm(text.pos_x);
+        let y = self.converter.px_to_mm(text.pos_y);  // Don't flip, already handled
+        let size = self.converter.px_to_mm(text.font_size);
+
+        // Symbol text angles are stored in tenths of a degree
+        format!(
+            "    (text \"{}\" (at {:.4} {:.4} {})\n      (effects (font (size {:.3} {:.3})))\n    )\n",
+            text.text.replace('"', "\\\""),
+            x,
+            y,
+            text.rotation * 10,
+            size,
+            size
+        )
+    }
+
+    fn format_text_v5(&self, text: &KiText) -> String {
+        let x = self.converter.px_to_mil(text.pos_x);
+        let y = self.converter.px_to_mil(text.pos_y);  // Don't flip, already handled
+        let size = self.converter.px_to_mil(text.font_size);
+
+        // T orientation posx posy size hidden unit convert text italic bold hjustify vjustify
+        format!(
+            "T {} {} {} {} 0 1 1 \"{}\" Normal 0 C C\n",
+            text.rotation * 10,
+            x,
+            y,
+            size,
+            text.text.replace('"', "''")
+        )
+    }
+
+    fn mm_to_mil(&self, mm: f64) -> i32 {
+        (mm / 0.0254).round() as i32
+    }
+
     fn rotation_to_direction(&self, rotation: i32) -> char {
         match rotation {
             0 => 'R',
@@ -379,3 +569,82 @@ impl SymbolExporter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arc(start: (f64, f64), mid: (f64, f64), end: (f64, f64)) -> KiArc {
+        KiArc {
+            start_x: start.0,
+            start_y: start.1,
+            mid_x: mid.0,
+            mid_y: mid.1,
+            end_x: end.0,
+            end_y: end.1,
+            stroke_width: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_format_arc_v5_counter_clockwise() {
+        let exporter = SymbolExporter::new(KicadVersion::V5);
+
+        // Upper half circle drawn from (10, 0) over (0, 10) to (-10, 0)
+        let line = exporter.format_arc_v5(&arc((10.0, 0.0), (0.0, 10.0), (-10.0, 0.0)));
+        assert_eq!(line, "A 0 0 100 0 1800 1 1 10 N 100 0 -100 0\n");
+    }
+
+    #[test]
+    fn test_format_arc_v5_swaps_clockwise() {
+        let exporter = SymbolExporter::new(KicadVersion::V5);
+
+        // Same endpoints through the lower half: endpoints are swapped to stay counter-clockwise
+        let line = exporter.format_arc_v5(&arc((10.0, 0.0), (0.0, -10.0), (-10.0, 0.0)));
+        assert_eq!(line, "A 0 0 100 1800 0 1 1 10 N -100 0 100 0\n");
+    }
+
+    fn drawing() -> KiSymbol {
+        KiSymbol {
+            beziers: vec![KiBezier { points: [(0.0, 0.0), (0.0, 5.0), (5.0, 5.0), (5.0, 0.0)], stroke_width: 1.0 }],
+            texts: vec![KiText { text: "Q\"1\"".to_string(), pos_x: 2.0, pos_y: -3.0, rotation: 90, font_size: 5.0 }],
+            ..KiSymbol::default()
+        }
+    }
+
+    #[test]
+    fn test_format_bezier_and_text_v5() {
+        let exporter = SymbolExporter::new(KicadVersion::V5);
+        let symbol = drawing();
+
+        assert_eq!(exporter.format_bezier_v5(&symbol.beziers[0]), "B 4 1 1 10 0 0 0 50 50 50 50 0 N\n");
+        assert_eq!(exporter.format_text_v5(&symbol.texts[0]), "T 900 20 -30 50 0 1 1 \"Q''1''\" Normal 0 C C\n");
+    }
+
+    #[test]
+    fn test_format_bezier_and_text_v6() {
+        let exporter = SymbolExporter::new(KicadVersion::V6);
+        let symbol = drawing();
+
+        let bezier = exporter.format_bezier_v6(&symbol.beziers[0]);
+        assert!(bezier.starts_with("    (bezier\n      (pts\n        (xy 0.0000 0.0000)\n        (xy 0.0000 1.2700)\n"));
+        assert!(bezier.contains("(stroke (width 0.2540) (type default))"));
+        assert_eq!(
+            exporter.format_text_v6(&symbol.texts[0]),
+            "    (text \"Q\\\"1\\\"\" (at 0.5080 -0.7620 900)\n      (effects (font (size 1.270 1.270)))\n    )\n"
+        );
+    }
+
+    #[test]
+    fn test_polyline_fill_types() {
+        let polyline = |fill| KiPolyline { points: vec![(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)], stroke_width: 1.0, fill };
+
+        let v5 = SymbolExporter::new(KicadVersion::V5);
+        assert!(v5.format_polyline_v5(&polyline(FillType::Outline)).ends_with(" F\n"));
+        assert!(v5.format_polyline_v5(&polyline(FillType::Background)).ends_with(" f\n"));
+        assert!(v5.format_polyline_v5(&polyline(FillType::None)).ends_with(" N\n"));
+
+        let v6 = SymbolExporter::new(KicadVersion::V6);
+        assert!(v6.format_polyline_v6(&polyline(FillType::Outline)).contains("(fill (type outline))"));
+    }
+}
@@ -0,0 +1,197 @@
+use crate::kicad::symbol::KiSymbol;
+use std::collections::HashMap;
+
+/// Tolerance (in EasyEDA px) below which a coordinate counts as on-grid
+const GRID_EPSILON: f64 = 1e-6;
+
+/// Result of a grid normalization pass
+#[derive(Debug, Clone, Default)]
+pub struct SnapReport {
+    /// Offset applied to the whole symbol (pins and graphics)
+    pub shift: (f64, f64),
+    /// Pins that needed an individual snap after the global shift
+    pub snapped: Vec<String>,
+    /// Pins left off-grid because snapping would overlap another pin
+    pub unsnapped: Vec<String>,
+}
+
+/// Snap symbol pins to a grid (in EasyEDA px, 1 px = 10 mil).
+///
+/// The whole symbol is first shifted by the offset that puts the largest
+/// number of pins on grid, so the body graphics stay aligned with the pins.
+/// Pins that are still off-grid are then snapped individually, unless the
+/// snapped position is already taken by another pin.
+pub fn snap_to_grid(symbol: &mut KiSymbol, grid: f64) -> SnapReport {
+    let mut report = SnapReport::default();
+
+    if symbol.pins.is_empty() || grid <= 0.0 {
+        return report;
+    }
+
+    let (dx, dy) = best_shift(symbol, grid);
+    if dx.abs() > GRID_EPSILON || dy.abs() > GRID_EPSILON {
+        shift_symbol(symbol, dx, dy);
+        report.shift = (dx, dy);
+    }
+
+    for idx in 0..symbol.pins.len() {
+        let pin = &symbol.pins[idx];
+        let target = (snap(pin.pos_x, grid), snap(pin.pos_y, grid));
+        if on_point((pin.pos_x, pin.pos_y), target) {
+            continue;
+        }
+
+        let label = format!("{} ({})", pin.number, pin.name);
+        let occupied = symbol.pins.iter().enumerate()
+            .any(|(other, p)| other != idx && on_point((p.pos_x, p.pos_y), target));
+
+        if occupied {
+            report.unsnapped.push(label);
+        } else {
+            symbol.pins[idx].pos_x = target.0;
+            symbol.pins[idx].pos_y = target.1;
+            report.snapped.push(label);
+        }
+    }
+
+    report
+}
+
+/// Pick the shift that brings the most pins onto the grid
+fn best_shift(symbol: &KiSymbol, grid: f64) -> (f64, f64) {
+    let mut votes: HashMap<(i64, i64), (usize, (f64, f64))> = HashMap::new();
+
+    for pin in &symbol.pins {
+        let dx = snap(pin.pos_x, grid) - pin.pos_x;
+        let dy = snap(pin.pos_y, grid) - pin.pos_y;
+        let key = ((dx * 1e4).round() as i64, (dy * 1e4).round() as i64);
+        votes.entry(key).or_insert((0, (dx, dy))).0 += 1;
+    }
+
+    // Ties are broken by the smallest shift to keep the result deterministic
+    votes.into_values()
+        .max_by(|(count_a, (ax, ay)), (count_b, (bx, by))| {
+            count_a.cmp(count_b)
+                .then_with(|| (bx.abs() + by.abs()).total_cmp(&(ax.abs() + ay.abs())))
+        })
+        .map(|(_, shift)| shift)
+        .unwrap_or((0.0, 0.0))
+}
+
+/// Translate every pin and graphic item of the symbol
+pub fn shift_symbol(symbol: &mut KiSymbol, dx: f64, dy: f64) {
+    for pin in &mut symbol.pins {
+        pin.pos_x += dx;
+        pin.pos_y += dy;
+    }
+    for rect in &mut symbol.rectangles {
+        rect.x1 += dx;
+        rect.y1 += dy;
+        rect.x2 += dx;
+        rect.y2 += dy;
+    }
+    for circle in &mut symbol.circles {
+        circle.cx += dx;
+        circle.cy += dy;
+    }
+    for arc in &mut symbol.arcs {
+        arc.start_x += dx;
+        arc.start_y += dy;
+        arc.mid_x += dx;
+        arc.mid_y += dy;
+        arc.end_x += dx;
+        arc.end_y += dy;
+    }
+    for polyline in &mut symbol.polylines {
+        for point in &mut polyline.points {
+            point.0 += dx;
+            point.1 += dy;
+        }
+    }
+    for bezier in &mut symbol.beziers {
+        for point in &mut bezier.points {
+            point.0 += dx;
+            point.1 += dy;
+        }
+    }
+    for text in &mut symbol.texts {
+        text.pos_x += dx;
+        text.pos_y += dy;
+    }
+}
+
+fn snap(value: f64, grid: f64) -> f64 {
+    (value / grid).round() * grid
+}
+
+fn on_point(a: (f64, f64), b: (f64, f64)) -> bool {
+    (a.0 - b.0).abs() < GRID_EPSILON && (a.1 - b.1).abs() < GRID_EPSILON
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kicad::symbol::*;
+
+    fn pin(number: &str, x: f64, y: f64) -> KiPin {
+        KiPin {
+            number: number.to_string(),
+            name: format!("P{}", number),
+            pin_type: PinType::Passive,
+            style: PinStyle::Line,
+            pos_x: x,
+            pos_y: y,
+            rotation: 0,
+            length: 10.0,
+            alternates: Vec::new(),
+        }
+    }
+
+    fn symbol(pins: Vec<KiPin>) -> KiSymbol {
+        KiSymbol {
+            name: "TEST".to_string(),
+            reference: "U".to_string(),
+            value: "TEST".to_string(),
+            footprint: String::new(),
+            datasheet: String::new(),
+            manufacturer: String::new(),
+            lcsc_id: String::new(),
+            jlc_id: String::new(),
+            pins,
+            rectangles: vec![KiRectangle { x1: 2.0, y1: 3.0, x2: 12.0, y2: 13.0, stroke_width: 1.0, fill: false }],
+            circles: Vec::new(),
+            arcs: Vec::new(),
+            polylines: Vec::new(),
+            beziers: Vec::new(),
+            texts: Vec::new(),
+            pin_layout: PinTextLayout::default(),
+        }
+    }
+
+    #[test]
+    fn test_shift_whole_symbol() {
+        let mut sym = symbol(vec![pin("1", 3.0, 4.0), pin("2", 13.0, 24.0)]);
+        let report = snap_to_grid(&mut sym, 10.0);
+
+        assert_eq!(report.shift, (-3.0, -4.0));
+        assert!(report.snapped.is_empty());
+        assert_eq!((sym.pins[1].pos_x, sym.pins[1].pos_y), (10.0, 20.0));
+        assert_eq!((sym.rectangles[0].x1, sym.rectangles[0].y1), (-1.0, -1.0));
+    }
+
+    #[test]
+    fn test_individual_snap_and_overlap() {
+        let mut sym = symbol(vec![
+            pin("1", 0.0, 0.0),
+            pin("2", 0.0, 10.0),
+            pin("3", 0.0, 12.0),
+            pin("4", 20.0, 4.0),
+        ]);
+        let report = snap_to_grid(&mut sym, 10.0);
+
+        assert_eq!(report.shift, (0.0, 0.0));
+        assert_eq!(report.unsnapped, vec!["3 (P3)".to_string()]);
+        assert_eq!(report.snapped, vec!["4 (P4)".to_string()]);
+        assert_eq!(sym.pins[3].pos_y, 0.0);
+    }
+}
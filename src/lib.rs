@@ -1,11 +1,16 @@
 pub mod cli;
+pub mod config;
 pub mod error;
 pub mod easyeda;
 pub mod kicad;
 pub mod converter;
+pub mod hash;
 pub mod library;
+pub mod lib_table;
+pub mod model_path;
 
 pub use cli::{Cli, KicadVersion};
+pub use config::Config;
 pub use error::{AppError, Result};
 pub use easyeda::{EasyedaApi, SymbolImporter, FootprintImporter};
 pub use kicad::{SymbolExporter, FootprintExporter, ModelExporter};
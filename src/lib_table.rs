@@ -0,0 +1,385 @@
+use crate::error::{KicadError, Result};
+use crate::model_path::ModelPathMode;
+use crate::kicad::footprint_exporter::escape;
+use crate::library::write_atomic;
+use regex::Regex;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Table version token. KiCad 7 introduced it and KiCad 6 fails to parse it; since
+/// `--kicad 6` and `--kicad 7` share one format, only KiCad 8 and 9 tables get it.
+/// KiCad 7 reads tables without the token and adds it when it saves them.
+const LIB_TABLE_VERSION: u32 = 7;
+
+/// KiCad settings file holding the path variables (Preferences > Configure Paths)
+const KICAD_COMMON: &str = "kicad_common.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LibTableKind {
+    Symbol,
+    Footprint,
+}
+
+impl LibTableKind {
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            LibTableKind::Symbol => "sym-lib-table",
+            LibTableKind::Footprint => "fp-lib-table",
+        }
+    }
+
+    fn root(&self) -> &'static str {
+        match self {
+            LibTableKind::Symbol => "sym_lib_table",
+            LibTableKind::Footprint => "fp_lib_table",
+        }
+    }
+}
+
+/// One `(lib ...)` row of a library table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibTableEntry {
+    pub name: String,
+    pub lib_type: String,
+    pub uri: String,
+    pub options: String,
+    pub descr: String,
+}
+
+impl LibTableEntry {
+    pub fn new(name: &str, lib_type: &str, uri: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            lib_type: lib_type.to_string(),
+            uri: uri.to_string(),
+            options: String::new(),
+            descr: String::new(),
+        }
+    }
+
+    fn to_sexpr(&self) -> String {
+        format!(
+            "(lib (name \"{}\")(type \"{}\")(uri \"{}\")(options \"{}\")(descr \"{}\"))",
+            escape(&self.name), escape(&self.lib_type), escape(&self.uri), escape(&self.options), escape(&self.descr)
+        )
+    }
+}
+
+/// What happened to a library or path variable during registration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Registration {
+    Added,
+    /// Already registered with the same location
+    Unchanged,
+    /// Registered elsewhere and replaced (--overwrite)
+    Updated,
+    /// Registered elsewhere and left alone; holds the existing location
+    Kept(String),
+}
+
+/// A symbol or footprint library found in the output directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputLibrary {
+    pub kind: LibTableKind,
+    pub name: String,
+    /// Plugin type for the table: "KiCad" or "Legacy"
+    pub lib_type: &'static str,
+    pub path: PathBuf,
+}
+
+/// Symbol libraries (`.kicad_sym`, or `.lib` without a `.kicad_sym` of the same
+/// name) and footprint libraries (`.pretty`) in the output directory that nlbn
+/// manages, i.e. whose nickname is one of `names`
+pub fn discover_libraries(output: &Path, names: &[&str]) -> Result<Vec<OutputLibrary>> {
+    let mut libraries = Vec::new();
+
+    for entry in fs::read_dir(output).map_err(KicadError::Io)? {
+        let path = entry.map_err(KicadError::Io)?.path();
+        let (Some(name), Some(extension)) = (path.file_stem(), path.extension()) else {
+            continue;
+        };
+        let name = name.to_string_lossy().to_string();
+        if !names.contains(&name.as_str()) {
+            continue;
+        }
+
+        let (kind, lib_type) = match extension.to_string_lossy().as_ref() {
+            "kicad_sym" if path.is_file() => (LibTableKind::Symbol, "KiCad"),
+            "lib" if path.is_file() && !path.with_extension("kicad_sym").exists() => (LibTableKind::Symbol, "Legacy"),
+            "pretty" if path.is_dir() => (LibTableKind::Footprint, "KiCad"),
+            _ => continue,
+        };
+        libraries.push(OutputLibrary { kind, name, lib_type, path });
+    }
+
+    libraries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(libraries)
+}
+
+/// URI of a library as written into a table, following the 3D model path mode so
+/// that libraries and models resolve the same way
+pub fn library_uri(path: &Path, mode: &ModelPathMode, project_dir: Option<&Path>) -> String {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+
+    match mode {
+        ModelPathMode::EnvVar(var) => {
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            return format!("${{{}}}/{}", var, file_name);
+        }
+        ModelPathMode::ProjectRelative => {
+            let project_dir = project_dir.map(|dir| std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf()));
+            if let Some(relative) = project_dir.as_deref().and_then(|dir| path.strip_prefix(dir).ok()) {
+                let parts: Vec<String> = relative.components()
+                    .map(|component| component.as_os_str().to_string_lossy().to_string())
+                    .collect();
+                return format!("${{KIPRJMOD}}/{}", parts.join("/"));
+            }
+        }
+        ModelPathMode::Absolute => {}
+    }
+
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Add libraries to the table file in `dir`, creating it if absent (written atomically)
+pub fn update_lib_table(dir: &Path, kind: LibTableKind, entries: &[LibTableEntry], overwrite: bool, versioned: bool) -> Result<Vec<(String, Registration)>> {
+    let path = dir.join(kind.file_name());
+    let existing = if path.exists() {
+        Some(fs::read_to_string(&path).map_err(KicadError::Io)?)
+    } else {
+        None
+    };
+
+    let (content, results) = merge_lib_table(existing.as_deref(), kind, entries, overwrite, versioned)?;
+    if existing.as_deref() != Some(content.as_str()) {
+        fs::create_dir_all(dir).map_err(KicadError::Io)?;
+        write_atomic(&path, &content)?;
+    }
+
+    Ok(results)
+}
+
+/// Merge entries into table text, keeping all other rows and formatting as they are.
+/// A row with the same name is replaced only when `overwrite` is set.
+pub fn merge_lib_table(existing: Option<&str>, kind: LibTableKind, entries: &[LibTableEntry], overwrite: bool, versioned: bool) -> Result<(String, Vec<(String, Registration)>)> {
+    let mut content = match existing {
+        Some(text) if !text.trim().is_empty() => text.to_string(),
+        _ => {
+            let version = if versioned { format!("  (version {})\n", LIB_TABLE_VERSION) } else { String::new() };
+            format!("({}\n{})\n", kind.root(), version)
+        }
+    };
+
+    if !content.trim_start().starts_with(&format!("({}", kind.root())) {
+        return Err(KicadError::LibTable(format!("{} is not a {} file", kind.file_name(), kind.root())).into());
+    }
+
+    let mut results = Vec::new();
+    for entry in entries {
+        let rows = parse_rows(&content);
+        let registration = match rows.iter().find(|(row, _)| row.name == entry.name) {
+            Some((row, _)) if row.uri == entry.uri => Registration::Unchanged,
+            Some((_, span)) if overwrite => {
+                content.replace_range(span.clone(), &entry.to_sexpr());
+                Registration::Updated
+            }
+            Some((row, _)) => Registration::Kept(row.uri.clone()),
+            None => {
+                // After the last row, or before the closing parenthesis of an empty table
+                let insert_at = match rows.last() {
+                    Some((_, span)) => span.end,
+                    None => content.rfind(')')
+                        .map(|end| content[..end].trim_end().len())
+                        .ok_or_else(|| KicadError::LibTable(format!("{} is not closed", kind.file_name())))?,
+                };
+                content.insert_str(insert_at, &format!("\n  {}", entry.to_sexpr()));
+                Registration::Added
+            }
+        };
+        results.push((entry.name.clone(), registration));
+    }
+
+    Ok((content, results))
+}
+
+/// Rows of a table with their byte spans
+fn parse_rows(content: &str) -> Vec<(LibTableEntry, Range<usize>)> {
+    let field = Regex::new(r#"\((name|type|uri|options|descr)\s+("(?:[^"\\]|\\.)*"|[^\s()]+)\s*\)"#).expect("valid regex");
+    let mut rows = Vec::new();
+
+    for span in top_level_lists(content) {
+        let block = &content[span.clone()];
+        if !block[1..].trim_start().starts_with("lib") || block[1..].trim_start().starts_with("lib_") {
+            continue;
+        }
+
+        let mut entry = LibTableEntry::new("", "", "");
+        for captures in field.captures_iter(block) {
+            let value = unquote(&captures[2]);
+            match &captures[1] {
+                "name" => entry.name = value,
+                "type" => entry.lib_type = value,
+                "uri" => entry.uri = value,
+                "options" => entry.options = value,
+                _ => entry.descr = value,
+            }
+        }
+        rows.push((entry, span));
+    }
+
+    rows
+}
+
+/// Spans of the lists directly inside the root list
+fn top_level_lists(content: &str) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (idx, c) in content.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '(' => {
+                depth += 1;
+                if depth == 2 {
+                    start = idx;
+                }
+            }
+            ')' => {
+                if depth == 2 {
+                    spans.push(start..idx + 1);
+                }
+                depth -= 1;
+            }
+            _ => {}
+        }
+    }
+
+    spans
+}
+
+/// Define a path variable in KiCad's common settings of a config directory, keeping all other settings
+pub fn set_path_variable(config_dir: &Path, var: &str, value: &Path, overwrite: bool) -> Result<Registration> {
+    let path = config_dir.join(KICAD_COMMON);
+    let value = std::path::absolute(value).unwrap_or_else(|_| value.to_path_buf())
+        .to_string_lossy()
+        .replace('\\', "/");
+
+    let mut settings: serde_json::Value = if path.exists() {
+        let content = fs::read_to_string(&path).map_err(KicadError::Io)?;
+        serde_json::from_str(&content)
+            .map_err(|e| KicadError::LibTable(format!("Failed to parse {}: {}", KICAD_COMMON, e)))?
+    } else {
+        serde_json::json!({})
+    };
+
+    let vars = settings.as_object_mut()
+        .map(|root| root.entry("environment").or_insert_with(|| serde_json::json!({})))
+        .and_then(|environment| {
+            if environment.is_null() {
+                *environment = serde_json::json!({});
+            }
+            environment.as_object_mut()
+        })
+        .map(|environment| environment.entry("vars").or_insert_with(|| serde_json::json!({})))
+        .and_then(|vars| {
+            if vars.is_null() {
+                *vars = serde_json::json!({});
+            }
+            vars.as_object_mut()
+        })
+        .ok_or_else(|| KicadError::LibTable(format!("Unexpected layout of {}", KICAD_COMMON)))?;
+
+    let registration = match vars.get(var).and_then(|current| current.as_str()) {
+        Some(current) if current == value => return Ok(Registration::Unchanged),
+        Some(current) if !overwrite => return Ok(Registration::Kept(current.to_string())),
+        Some(_) => Registration::Updated,
+        None => Registration::Added,
+    };
+    vars.insert(var.to_string(), serde_json::Value::String(value));
+
+    let content = serde_json::to_string_pretty(&settings)
+        .map_err(|e| KicadError::LibTable(format!("Failed to serialize {}: {}", KICAD_COMMON, e)))?;
+    fs::create_dir_all(config_dir).map_err(KicadError::Io)?;
+    write_atomic(&path, &(content + "\n"))?;
+
+    Ok(registration)
+}
+
+fn unquote(value: &str) -> String {
+    match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(inner) => inner.replace("\\\"", "\"").replace("\\\\", "\\"),
+        None => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TABLE: &str = "(fp_lib_table\n  (version 7)\n  (lib (name \"Mine\")(type \"KiCad\")(uri \"${KIPRJMOD}/mine.pretty\")(options \"\")(descr \"keep (me)\"))\n  (lib (name nlbn)(type KiCad)(uri /old/nlbn.pretty)(options \"\")(descr \"\"))\n)\n";
+
+    #[test]
+    fn test_merge_keeps_existing_rows() {
+        let entries = [
+            LibTableEntry::new("nlbn", "KiCad", "${KIPRJMOD}/libs/nlbn.pretty"),
+            LibTableEntry::new("passives", "KiCad", "${KIPRJMOD}/libs/passives.pretty"),
+        ];
+
+        let (content, results) = merge_lib_table(Some(TABLE), LibTableKind::Footprint, &entries, false, true).unwrap();
+        assert_eq!(results[0].1, Registration::Kept("/old/nlbn.pretty".to_string()));
+        assert_eq!(results[1].1, Registration::Added);
+        assert!(content.starts_with(&TABLE[..TABLE.len() - 2]));
+        assert!(content.ends_with("(uri \"${KIPRJMOD}/libs/passives.pretty\")(options \"\")(descr \"\"))\n)\n"));
+
+        let (content, results) = merge_lib_table(Some(&content), LibTableKind::Footprint, &entries, true, true).unwrap();
+        assert_eq!(results[0].1, Registration::Updated);
+        assert_eq!(results[1].1, Registration::Unchanged);
+        assert!(content.contains("descr \"keep (me)\""));
+        assert!(!content.contains("/old/nlbn.pretty"));
+        assert_eq!(parse_rows(&content).len(), 3);
+    }
+
+    #[test]
+    fn test_new_table_and_uris() {
+        let entries = [LibTableEntry::new("nlbn", "KiCad", "/libs/nlbn.kicad_sym")];
+        let (content, _) = merge_lib_table(None, LibTableKind::Symbol, &entries, false, false).unwrap();
+        assert_eq!(content, "(sym_lib_table\n  (lib (name \"nlbn\")(type \"KiCad\")(uri \"/libs/nlbn.kicad_sym\")(options \"\")(descr \"\"))\n)\n");
+        assert!(merge_lib_table(Some("(fp_lib_table\n)"), LibTableKind::Symbol, &entries, false, false).is_err());
+
+        let project = Path::new("/work/board");
+        let library = project.join("libs").join("nlbn.kicad_sym");
+        assert_eq!(library_uri(&library, &ModelPathMode::ProjectRelative, Some(project)), "${KIPRJMOD}/libs/nlbn.kicad_sym");
+        assert_eq!(library_uri(&library, &ModelPathMode::EnvVar("NLBN_LIB_DIR".to_string()), None), "${NLBN_LIB_DIR}/nlbn.kicad_sym");
+        assert_eq!(library_uri(&library, &ModelPathMode::ProjectRelative, None), "/work/board/libs/nlbn.kicad_sym");
+    }
+
+    #[test]
+    fn test_discover_managed_libraries() {
+        let output = std::env::temp_dir().join(format!("nlbn-discover-{}", std::process::id()));
+        fs::create_dir_all(output.join("nlbn.pretty")).unwrap();
+        fs::create_dir_all(output.join("vendor.pretty")).unwrap();
+        fs::write(output.join("nlbn.kicad_sym"), "").unwrap();
+        fs::write(output.join("passives.lib"), "").unwrap();
+        fs::write(output.join("vendor.kicad_sym"), "").unwrap();
+
+        let libraries = discover_libraries(&output, &["nlbn", "passives"]).unwrap();
+        let found: Vec<(&str, &str)> = libraries.iter().map(|l| (l.name.as_str(), l.lib_type)).collect();
+        assert_eq!(found, vec![("nlbn", "KiCad"), ("nlbn", "KiCad"), ("passives", "Legacy")]);
+
+        fs::remove_dir_all(&output).unwrap();
+    }
+}
@@ -1,22 +1,126 @@
-use crate::error::{KicadError, Result};
+use crate::error::{AppError, KicadError, Result};
+use crate::hash::fnv1a_64;
+use crate::model_path::{ModelFormat, ModelPaths};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 static SYMBOL_WRITE_LOCK: Mutex<()> = Mutex::new(());
+static MODEL_MANIFEST_LOCK: Mutex<()> = Mutex::new(());
+static LOCK_FILE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Library name used when neither --lib-name nor a routing rule picks one
+pub const DEFAULT_LIB_NAME: &str = "nlbn";
+
+/// Manifest of the shared 3D models, stored in the model directory
+const MODEL_MANIFEST: &str = "models.json";
+
+/// Provenance manifest of the converted parts, stored in the output directory
+pub const LOCK_FILE: &str = "nlbn.lock.json";
+
+/// A written library item and the hash of its content
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockedItem {
+    /// Symbol name, or file path relative to the output directory
+    pub name: String,
+    /// Content hash (hex FNV-1a)
+    pub hash: String,
+}
+
+impl LockedItem {
+    pub fn new(name: &str, data: &[u8]) -> Self {
+        Self {
+            name: name.to_string(),
+            hash: format!("{:016x}", fnv1a_64(data)),
+        }
+    }
+}
+
+/// How one part was produced, as recorded in the lock file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LockEntry {
+    pub library: String,
+    pub symbol: Option<LockedItem>,
+    pub footprint: Option<LockedItem>,
+    pub model: Option<ModelEntry>,
+    /// When the EasyEDA data was fetched (UTC, RFC 3339)
+    pub fetched_at: String,
+    /// EasyEDA update time of the part
+    pub upstream_revision: String,
+    /// Hash of the fetched EasyEDA data
+    pub upstream_hash: String,
+    pub nlbn_version: String,
+    /// Conversion options that affect the output
+    pub options: BTreeMap<String, String>,
+}
+
+impl LockEntry {
+    /// Entry for a part fetched just now; items are filled in as they are written
+    pub fn new(library: &str, upstream_revision: &str, upstream_data: &[u8], options: BTreeMap<String, String>) -> Self {
+        Self {
+            library: library.to_string(),
+            fetched_at: format_timestamp(SystemTime::now()),
+            upstream_revision: upstream_revision.to_string(),
+            upstream_hash: format!("{:016x}", fnv1a_64(upstream_data)),
+            nlbn_version: env!("CARGO_PKG_VERSION").to_string(),
+            options,
+            ..Self::default()
+        }
+    }
+}
+
+/// Model files used by one part, as recorded in the model manifest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelEntry {
+    /// File stem shared by all formats of the model
+    pub name: String,
+    /// Hash of the model source: STEP content, or the EasyEDA model uuid without STEP (hex FNV-1a)
+    pub hash: String,
+    /// Written files, relative to the model directory, with their content hashes
+    pub files: Vec<LockedItem>,
+}
+
+/// A symbol as stored in the library
+#[derive(Debug, Clone, PartialEq)]
+pub struct WrittenSymbol {
+    /// Symbol text written to the library (the derived form when `base` is set)
+    pub data: String,
+    /// Base symbol this one extends
+    pub base: Option<String>,
+}
 
 pub struct LibraryManager {
     output_path: PathBuf,
+    lib_name: String,
 }
 
 impl LibraryManager {
-    pub fn new(output_path: &Path) -> Self {
+    pub fn new(output_path: &Path, lib_name: &str) -> Self {
         Self {
             output_path: output_path.to_path_buf(),
+            lib_name: lib_name.to_string(),
         }
     }
 
+    /// Manager for another library in the same output directory
+    pub fn with_library(&self, lib_name: &str) -> Self {
+        Self::new(&self.output_path, lib_name)
+    }
+
+    pub fn lib_name(&self) -> &str {
+        &self.lib_name
+    }
+
+    /// Library-qualified footprint reference for the symbol's Footprint field
+    pub fn footprint_ref(&self, footprint_name: &str) -> String {
+        format!("{}:{}", self.lib_name, footprint_name)
+    }
+
     /// Create necessary output directories
     pub fn create_directories(&self) -> Result<()> {
         // Create main output directory
@@ -24,12 +128,12 @@ impl LibraryManager {
             .map_err(KicadError::Io)?;
 
         // Create .pretty directory for footprints
-        let pretty_dir = self.output_path.join("nlbn.pretty");
+        let pretty_dir = self.pretty_dir();
         fs::create_dir_all(&pretty_dir)
             .map_err(KicadError::Io)?;
 
         // Create .3dshapes directory for 3D models
-        let shapes_dir = self.output_path.join("nlbn.3dshapes");
+        let shapes_dir = self.shapes_dir();
         fs::create_dir_all(&shapes_dir)
             .map_err(KicadError::Io)?;
 
@@ -48,42 +152,29 @@ impl LibraryManager {
 
         // Check for v6 format
         let v6_pattern = format!(r#"\(symbol\s+"{}""#, regex::escape(component_name));
-        if let Ok(re) = Regex::new(&v6_pattern) {
-            if re.is_match(&content) {
-                return Ok(true);
-            }
+        if let Ok(re) = Regex::new(&v6_pattern)
+            && re.is_match(&content) {
+            return Ok(true);
         }
 
         // Check for v5 format
         let v5_pattern = format!(r"DEF\s+{}\s+", regex::escape(component_name));
-        if let Ok(re) = Regex::new(&v5_pattern) {
-            if re.is_match(&content) {
-                return Ok(true);
-            }
+        if let Ok(re) = Regex::new(&v5_pattern)
+            && re.is_match(&content) {
+            return Ok(true);
         }
 
         Ok(false)
     }
 
-    /// Add or update a component in the library file (thread-safe)
-    pub fn add_or_update_component(&self, lib_path: &Path, component_name: &str, component_data: &str, overwrite: bool) -> Result<()> {
+    /// Add or update a component in the library file (thread-safe).
+    /// Returns what was written, or None when an existing entry was kept.
+    pub fn add_or_update_component(&self, lib_path: &Path, component_name: &str, component_data: &str, overwrite: bool) -> Result<Option<WrittenSymbol>> {
         // Lock to prevent concurrent writes and check-then-act race conditions
         let _lock = SYMBOL_WRITE_LOCK.lock().unwrap();
 
         // Check if component exists (within lock to prevent TOCTOU)
-        let exists = if lib_path.exists() {
-            let content = fs::read_to_string(lib_path)
-                .map_err(KicadError::Io)?;
-
-            let v6_pattern = format!(r#"\(symbol\s+"{}""#, regex::escape(component_name));
-            if let Ok(re) = Regex::new(&v6_pattern) {
-                re.is_match(&content)
-            } else {
-                false
-            }
-        } else {
-            false
-        };
+        let exists = self.component_exists(lib_path, component_name)?;
 
         if exists && overwrite {
             // Update existing component
@@ -91,10 +182,92 @@ impl LibraryManager {
         } else if !exists {
             // Add new component
             self.add_component_internal(lib_path, component_data)?;
+        } else {
+            // Exists and !overwrite: keep the library entry
+            return Ok(None);
         }
-        // If exists and !overwrite, do nothing
 
-        Ok(())
+        Ok(Some(WrittenSymbol { data: component_data.to_string(), base: None }))
+    }
+
+    /// Add or update a component, storing it as a derived symbol `(extends "...")` when
+    /// the library already holds a symbol with identical graphics and pins (thread-safe).
+    /// `derive` renders the derived form for a base symbol name. Returns what was written,
+    /// or None when the existing entry was kept.
+    pub fn add_or_update_derived_component<F>(&self, lib_path: &Path, component_name: &str, component_data: &str, overwrite: bool, derive: F) -> Result<Option<WrittenSymbol>>
+    where
+        F: FnOnce(&str) -> Result<String>,
+    {
+        let _lock = SYMBOL_WRITE_LOCK.lock().unwrap();
+
+        let content = if lib_path.exists() {
+            fs::read_to_string(lib_path)
+                .map_err(KicadError::Io)?
+        } else {
+            String::new()
+        };
+
+        let blocks = symbol_blocks(&content);
+        let existing = blocks.iter().find(|b| b.name == component_name);
+
+        if existing.is_some() && !overwrite {
+            return Ok(None);
+        }
+
+        // A symbol that others extend must stay a full symbol
+        let extends_marker = format!("(extends \"{}\")", component_name);
+        let derived: Vec<&str> = blocks.iter()
+            .filter(|b| content[b.start..b.end].contains(&extends_marker))
+            .map(|b| b.name.as_str())
+            .collect();
+        let is_base = !derived.is_empty();
+
+        // Changing the graphics of a base would silently change every symbol derived from it
+        if let Some(block) = existing
+            && is_base
+            && symbol_body_hash(&content[block.start..block.end], component_name) != symbol_body_hash(component_data, component_name)
+        {
+            log::warn!(
+                "Not overwriting {}: its graphics changed and {} derive from it",
+                component_name,
+                derived.join(", ")
+            );
+            return Ok(None);
+        }
+
+        let base = if is_base {
+            None
+        } else {
+            symbol_body_hash(component_data, component_name).and_then(|hash| {
+                blocks.iter().find(|b| {
+                    b.name != component_name
+                        && symbol_body_hash(&content[b.start..b.end], &b.name) == Some(hash)
+                })
+            })
+        };
+
+        let data = match base {
+            Some(base_block) => derive(&base_block.name)?,
+            None => component_data.to_string(),
+        };
+
+        match existing {
+            // Derived symbols must come after their base, so move the entry if needed
+            Some(block) if base.is_none_or(|b| b.start < block.start) => {
+                let new_content = format!("{}{}{}", &content[..block.start], data.trim(), &content[block.end..]);
+                fs::write(lib_path, new_content)
+                    .map_err(KicadError::Io)?;
+            }
+            Some(block) => {
+                let new_content = format!("{}{}", &content[..block.start].trim_end(), &content[block.end..]);
+                fs::write(lib_path, new_content)
+                    .map_err(KicadError::Io)?;
+                self.add_component_internal(lib_path, &data)?;
+            }
+            None => self.add_component_internal(lib_path, &data)?,
+        }
+
+        Ok(Some(WrittenSymbol { data, base: base.map(|b| b.name.clone()) }))
     }
 
     /// Internal add component (assumes lock is held)
@@ -131,30 +304,23 @@ impl LibraryManager {
         let content = fs::read_to_string(lib_path)
             .map_err(KicadError::Io)?;
 
-        let v6_pattern = format!(
-            r#"\(symbol\s+"{}"\s+.*?\n\)\n"#,
-            regex::escape(component_name)
-        );
-        if let Ok(re) = Regex::new(&v6_pattern) {
-            if re.is_match(&content) {
-                let new_content = re.replace(&content, new_data);
-                fs::write(lib_path, new_content.as_ref())
-                    .map_err(KicadError::Io)?;
-                return Ok(());
-            }
+        if let Some(block) = symbol_blocks(&content).into_iter().find(|b| b.name == component_name) {
+            let new_content = format!("{}{}{}", &content[..block.start], new_data.trim(), &content[block.end..]);
+            fs::write(lib_path, new_content)
+                .map_err(KicadError::Io)?;
+            return Ok(());
         }
 
         let v5_pattern = format!(
-            r"DEF\s+{}\s+.*?ENDDEF\n",
+            r"(?s)DEF\s+{}\s+.*?ENDDEF\n",
             regex::escape(component_name)
         );
-        if let Ok(re) = Regex::new(&v5_pattern) {
-            if re.is_match(&content) {
-                let new_content = re.replace(&content, new_data);
-                fs::write(lib_path, new_content.as_ref())
-                    .map_err(KicadError::Io)?;
-                return Ok(());
-            }
+        if let Ok(re) = Regex::new(&v5_pattern)
+            && re.is_match(&content) {
+            let new_content = re.replace(&content, new_data);
+            fs::write(lib_path, new_content.as_ref())
+                .map_err(KicadError::Io)?;
+            return Ok(());
         }
 
         Err(KicadError::SymbolExport(format!("Component {} not found in library", component_name)).into())
@@ -204,44 +370,27 @@ impl LibraryManager {
         // Lock to prevent concurrent writes to the same symbol library file
         let _lock = SYMBOL_WRITE_LOCK.lock().unwrap();
 
-        let content = fs::read_to_string(lib_path)
-            .map_err(KicadError::Io)?;
+        self.update_component_internal(lib_path, component_name, new_data)
+    }
 
-        // Try v6 format first
-        let v6_pattern = format!(
-            r#"\(symbol\s+"{}"\s+.*?\n\)\n"#,
-            regex::escape(component_name)
-        );
-        if let Ok(re) = Regex::new(&v6_pattern) {
-            if re.is_match(&content) {
-                let new_content = re.replace(&content, new_data);
-                fs::write(lib_path, new_content.as_ref())
-                    .map_err(KicadError::Io)?;
-                return Ok(());
-            }
-        }
+    /// Directory holding the 3D model files
+    pub fn shapes_dir(&self) -> PathBuf {
+        self.output_path.join(format!("{}.3dshapes", self.lib_name))
+    }
 
-        // Try v5 format
-        let v5_pattern = format!(
-            r"DEF\s+{}\s+.*?ENDDEF\n",
-            regex::escape(component_name)
-        );
-        if let Ok(re) = Regex::new(&v5_pattern) {
-            if re.is_match(&content) {
-                let new_content = re.replace(&content, new_data);
-                fs::write(lib_path, new_content.as_ref())
-                    .map_err(KicadError::Io)?;
-                return Ok(());
-            }
-        }
+    /// Directory holding the footprint files
+    pub fn pretty_dir(&self) -> PathBuf {
+        self.output_path.join(format!("{}.pretty", self.lib_name))
+    }
 
-        Err(KicadError::SymbolExport(format!("Component {} not found in library", component_name)).into())
+    /// Footprint file path relative to the output directory
+    pub fn footprint_file(&self, footprint_name: &str) -> String {
+        format!("{}.pretty/{}.kicad_mod", self.lib_name, footprint_name)
     }
 
     /// Write a footprint file
     pub fn write_footprint(&self, footprint_name: &str, data: &str) -> Result<PathBuf> {
-        let pretty_dir = self.output_path.join("nlbn.pretty");
-        let footprint_path = pretty_dir.join(format!("{}.kicad_mod", footprint_name));
+        let footprint_path = self.output_path.join(self.footprint_file(footprint_name));
 
         fs::write(&footprint_path, data)
             .map_err(KicadError::Io)?;
@@ -253,7 +402,7 @@ impl LibraryManager {
 
     /// Write 3D model files
     pub fn write_3d_model(&self, model_name: &str, wrl_data: &str, step_data: &[u8]) -> Result<(PathBuf, PathBuf)> {
-        let shapes_dir = self.output_path.join("nlbn.3dshapes");
+        let shapes_dir = self.shapes_dir();
 
         // Write VRML file
         let wrl_path = shapes_dir.join(format!("{}.wrl", model_name));
@@ -275,7 +424,7 @@ impl LibraryManager {
 
     /// Write only VRML model (when STEP is not available)
     pub fn write_wrl_model(&self, model_name: &str, wrl_data: &str) -> Result<PathBuf> {
-        let shapes_dir = self.output_path.join("nlbn.3dshapes");
+        let shapes_dir = self.shapes_dir();
 
         // Write VRML file
         let wrl_path = shapes_dir.join(format!("{}.wrl", model_name));
@@ -289,7 +438,7 @@ impl LibraryManager {
 
     /// Write only STEP model
     pub fn write_step_model(&self, model_name: &str, step_data: &[u8]) -> Result<PathBuf> {
-        let shapes_dir = self.output_path.join("nlbn.3dshapes");
+        let shapes_dir = self.shapes_dir();
 
         // Write STEP file
         let step_path = shapes_dir.join(format!("{}.step", model_name));
@@ -301,12 +450,356 @@ impl LibraryManager {
         Ok(step_path)
     }
 
+    /// Store a part's 3D model files, sharing files between parts with identical models.
+    ///
+    /// Files are named by package and a hash of `source`, the identity of the model:
+    /// the STEP content when a STEP file is stored, otherwise the EasyEDA model uuid.
+    /// Every format of one model shares a name. A file already on disk is reused only when the manifest records the same
+    /// content hash for it. The part is recorded in the model manifest either way.
+    pub fn store_model(&self, lcsc_id: &str, package: &str, source: &[u8], files: &[(ModelFormat, Vec<u8>)]) -> Result<ModelEntry> {
+        let hash = format!("{:016x}", fnv1a_64(source));
+
+        let _lock = MODEL_MANIFEST_LOCK.lock().unwrap();
+        let mut manifest = self.read_model_manifest()?;
+
+        // Another model with the same short name gets the full hash
+        let mut name = ModelPaths::model_name(package, &hash);
+        if manifest.values().any(|entry| entry.name == name && entry.hash != hash) {
+            name = format!("{}{}", name, &hash[8..]);
+        }
+
+        let mut entry = ModelEntry { name, hash, files: Vec::new() };
+        for (format, data) in files {
+            let file = LockedItem::new(&format!("{}.{}", entry.name, format.extension()), data);
+            let path = self.shapes_dir().join(&file.name);
+            let recorded = manifest.values().flat_map(|e| e.files.iter()).find(|f| f.name == file.name);
+            if path.exists() && recorded.is_some_and(|f| f.hash == file.hash) {
+                log::info!("Reusing 3D model: {}", path.display());
+            } else {
+                write_atomic(&path, data)?;
+                log::info!("Wrote 3D model: {}", path.display());
+            }
+            entry.files.push(file);
+        }
+
+        manifest.insert(lcsc_id.to_string(), entry.clone());
+        self.write_model_manifest(&manifest)?;
+
+        Ok(entry)
+    }
+
+    /// Model manifest (LCSC ID -> model files); empty if none was written yet
+    pub fn read_model_manifest(&self) -> Result<BTreeMap<String, ModelEntry>> {
+        let path = self.shapes_dir().join(MODEL_MANIFEST);
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        let content = fs::read_to_string(&path)
+            .map_err(KicadError::Io)?;
+        serde_json::from_str(&content)
+            .map_err(|e| KicadError::ModelExport(format!("Invalid model manifest {}: {}", path.display(), e)).into())
+    }
+
+    fn write_model_manifest(&self, manifest: &BTreeMap<String, ModelEntry>) -> Result<()> {
+        let path = self.shapes_dir().join(MODEL_MANIFEST);
+        let content = serde_json::to_string_pretty(manifest)
+            .map_err(|e| KicadError::ModelExport(format!("Failed to serialize model manifest: {}", e)))?;
+
+        write_atomic(&path, content + "\n")
+    }
+
+    /// Record how a part was produced in the lock file (thread-safe, written atomically).
+    /// Items not produced in this run keep their previous record.
+    pub fn record_component(&self, lcsc_id: &str, mut entry: LockEntry) -> Result<()> {
+        let _lock = LOCK_FILE_LOCK.lock().unwrap();
+
+        let mut lock = self.read_lock()?;
+        if let Some(previous) = lock.remove(lcsc_id) {
+            entry.symbol = entry.symbol.or(previous.symbol);
+            entry.footprint = entry.footprint.or(previous.footprint);
+            entry.model = entry.model.or(previous.model);
+        }
+        lock.insert(lcsc_id.to_string(), entry);
+
+        let path = self.output_path.join(LOCK_FILE);
+        let content = serde_json::to_string_pretty(&lock)
+            .map_err(|e| AppError::Other(format!("Failed to serialize {}: {}", LOCK_FILE, e)))?;
+        write_atomic(&path, content + "\n")
+    }
+
+    /// Lock file entries by LCSC ID; empty when no lock file exists yet
+    pub fn read_lock(&self) -> Result<BTreeMap<String, LockEntry>> {
+        let path = self.output_path.join(LOCK_FILE);
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        let content = fs::read_to_string(&path)
+            .map_err(KicadError::Io)?;
+        serde_json::from_str(&content)
+            .map_err(|e| AppError::Other(format!("Failed to parse {}: {}", LOCK_FILE, e)))
+    }
+
     /// Get the symbol library path
     pub fn get_symbol_lib_path(&self, v5: bool) -> PathBuf {
         if v5 {
-            self.output_path.join("nlbn.lib")
+            self.output_path.join(format!("{}.lib", self.lib_name))
         } else {
-            self.output_path.join("nlbn.kicad_sym")
+            self.output_path.join(format!("{}.kicad_sym", self.lib_name))
         }
     }
 }
+
+/// UTC time as RFC 3339, e.g. "2024-01-31T12:00:00Z"
+fn format_timestamp(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, rem) = ((secs / 86_400) as i64, secs % 86_400);
+
+    // Civil date from days since 1970-01-01 (proleptic Gregorian calendar)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, rem / 3600, rem % 3600 / 60, rem % 60
+    )
+}
+
+/// Check a library nickname; it ends up in file names and `lib:item` references
+pub fn validate_lib_name(name: &str) -> std::result::Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("library name must not be empty".to_string());
+    }
+    if let Some(c) = name.chars().find(|c| matches!(c, ':' | '/' | '\\' | '"') || c.is_control()) {
+        return Err(format!("library name '{}' must not contain {:?}", name, c));
+    }
+    Ok(())
+}
+
+/// Replace characters that are unsafe in library item and file names with '_'
+pub fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// A top-level `(symbol "name" ...)` entry of a v6 symbol library (byte range)
+struct SymbolBlock {
+    name: String,
+    start: usize,
+    end: usize,
+}
+
+/// Locate the top-level symbols of a v6 symbol library
+fn symbol_blocks(content: &str) -> Vec<SymbolBlock> {
+    let name_re = Regex::new(r#"^\(symbol\s+"((?:[^"\\]|\\.)*)""#).unwrap();
+    let mut blocks = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, byte) in content.bytes().enumerate() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if byte == b'\\' {
+                escaped = true;
+            } else if byte == b'"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match byte {
+            b'"' => in_string = true,
+            b'(' => {
+                depth += 1;
+                if depth == 2 {
+                    start = i;
+                }
+            }
+            b')' => {
+                if depth == 2
+                    && let Some(cap) = name_re.captures(&content[start..=i]) {
+                    blocks.push(SymbolBlock {
+                        name: cap[1].to_string(),
+                        start,
+                        end: i + 1,
+                    });
+                }
+                depth = depth.saturating_sub(1);
+            }
+            _ => {}
+        }
+    }
+
+    blocks
+}
+
+/// Write next to the target and rename, so readers never see a partial file
+pub(crate) fn write_atomic(path: &Path, content: impl AsRef<[u8]>) -> Result<()> {
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    fs::write(&temp_path, content).map_err(KicadError::Io)?;
+    fs::rename(&temp_path, path).map_err(KicadError::Io)?;
+    Ok(())
+}
+
+/// Hash the pin text settings, graphics and pins of a full (non-derived) v6 symbol,
+/// ignoring its name, properties and formatting
+fn symbol_body_hash(block: &str, name: &str) -> Option<u64> {
+    if block.contains("(extends ") {
+        return None;
+    }
+
+    let header_start = block.find(&format!("(symbol \"{}\"", name))?;
+    let unit_marker = format!("(symbol \"{}_0_1\"", name);
+    let unit_start = block.find(&unit_marker)?;
+    let header = &block[header_start..unit_start];
+    let header = &header[..header.find("(property").unwrap_or(header.len())];
+    let body = &block[unit_start + unit_marker.len()..];
+
+    let normalized: Vec<&str> = header.split_whitespace().skip(2)
+        .chain(body.split_whitespace())
+        .collect();
+
+    Some(fnv1a_64(normalized.join(" ").as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIBRARY: &str = "(kicad_symbol_lib\n  (version 20211014)\n  (generator nlbn)\n  (symbol \"R_10k\"\n    (in_bom yes)\n    (property \"Value\" \"10k\")\n    (symbol \"R_10k_0_1\"\n      (rectangle (start 0 0) (end 1 2))\n    )\n  )\n  (symbol \"R_1k\" (extends \"R_10k\")\n    (property \"Value\" \"1k (\\\"x\\\")\")\n  )\n)\n";
+
+    #[test]
+    fn test_symbol_blocks() {
+        let blocks = symbol_blocks(LIBRARY);
+        let names: Vec<&str> = blocks.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec!["R_10k", "R_1k"]);
+        assert!(LIBRARY[blocks[1].start..blocks[1].end].ends_with("\")\n  )"));
+    }
+
+    #[test]
+    fn test_symbol_body_hash() {
+        let blocks = symbol_blocks(LIBRARY);
+        let base = &LIBRARY[blocks[0].start..blocks[0].end];
+        let renamed = "(symbol \"R_4k7\"\n (in_bom yes) (property \"Value\" \"4k7\")\n (symbol \"R_4k7_0_1\" (rectangle (start 0 0) (end 1 2))\n )\n )";
+
+        assert!(symbol_body_hash(base, "R_10k").is_some());
+        assert_eq!(symbol_body_hash(base, "R_10k"), symbol_body_hash(renamed, "R_4k7"));
+        assert_eq!(symbol_body_hash(&LIBRARY[blocks[1].start..blocks[1].end], "R_1k"), None);
+    }
+
+    #[test]
+    fn test_base_with_changed_graphics_is_kept() {
+        let output = std::env::temp_dir().join(format!("nlbn-derive-{}", std::process::id()));
+        let manager = LibraryManager::new(&output, DEFAULT_LIB_NAME);
+        manager.create_directories().unwrap();
+        let lib_path = manager.get_symbol_lib_path(false);
+        fs::write(&lib_path, LIBRARY).unwrap();
+
+        let same = "(symbol \"R_10k\"\n (in_bom yes) (property \"Value\" \"10 kOhm\")\n (symbol \"R_10k_0_1\" (rectangle (start 0 0) (end 1 2))\n )\n )";
+        let changed = "(symbol \"R_10k\"\n (in_bom yes) (property \"Value\" \"10k\")\n (symbol \"R_10k_0_1\" (rectangle (start 0 0) (end 2 4))\n )\n )";
+        let derive = |_: &str| -> Result<String> { unreachable!() };
+
+        assert_eq!(manager.add_or_update_derived_component(&lib_path, "R_10k", changed, false, derive).unwrap(), None);
+        assert_eq!(manager.add_or_update_derived_component(&lib_path, "R_10k", changed, true, derive).unwrap(), None);
+        assert_eq!(fs::read_to_string(&lib_path).unwrap(), LIBRARY);
+
+        let written = manager.add_or_update_derived_component(&lib_path, "R_10k", same, true, derive).unwrap().unwrap();
+        assert_eq!(written.base, None);
+        assert!(fs::read_to_string(&lib_path).unwrap().contains("10 kOhm"));
+
+        fs::remove_dir_all(&output).unwrap();
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        let time = UNIX_EPOCH + std::time::Duration::from_secs(1_709_210_096);
+        assert_eq!(format_timestamp(time), "2024-02-29T12:34:56Z");
+    }
+
+    #[test]
+    fn test_record_component_keeps_other_items() {
+        let output = std::env::temp_dir().join(format!("nlbn-lock-{}", std::process::id()));
+        let manager = LibraryManager::new(&output, DEFAULT_LIB_NAME);
+        manager.create_directories().unwrap();
+
+        let mut entry = LockEntry::new("nlbn", "1700000000", b"{}", BTreeMap::new());
+        entry.symbol = Some(LockedItem::new("R_10k_C25804", b"(symbol)"));
+        manager.record_component("C25804", entry).unwrap();
+
+        let mut entry = LockEntry::new("nlbn", "1700000001", b"{ }", BTreeMap::new());
+        entry.footprint = Some(LockedItem::new(&manager.footprint_file("R0603"), b"(footprint)"));
+        manager.record_component("C25804", entry).unwrap();
+
+        let lock = manager.read_lock().unwrap();
+        let entry = &lock["C25804"];
+        assert_eq!(entry.symbol.as_ref().unwrap().name, "R_10k_C25804");
+        assert_eq!(entry.footprint.as_ref().unwrap().name, "nlbn.pretty/R0603.kicad_mod");
+        assert_eq!(entry.upstream_revision, "1700000001");
+        assert_eq!(entry.nlbn_version, env!("CARGO_PKG_VERSION"));
+        assert!(!output.join("nlbn.lock.json.tmp").exists());
+
+        fs::remove_dir_all(&output).unwrap();
+    }
+
+    #[test]
+    fn test_store_model_deduplicates() {
+        let output = std::env::temp_dir().join(format!("nlbn-models-{}", std::process::id()));
+        let manager = LibraryManager::new(&output, DEFAULT_LIB_NAME);
+        manager.create_directories().unwrap();
+
+        let step = vec![(ModelFormat::Step, b"ISO-10303-21; 0402".to_vec())];
+        let first = manager.store_model("C25744", "R0402", b"uuid-0402", &step).unwrap();
+        let second = manager.store_model("C25076", "R0402", b"uuid-0402", &step).unwrap();
+        let other = manager.store_model("C1525", "C0402", b"uuid-cap", &[(ModelFormat::Step, b"ISO-10303-21; cap".to_vec())]).unwrap();
+
+        assert_eq!(first, second);
+        assert!(first.name.starts_with("R0402_"));
+        assert_ne!(first.name, other.name);
+
+        // Adding a format keeps the name of the model
+        let both = vec![(ModelFormat::Wrl, b"#VRML V2.0 utf8".to_vec()), step[0].clone()];
+        let third = manager.store_model("C25744", "R0402", b"uuid-0402", &both).unwrap();
+        assert_eq!(third.name, first.name);
+        assert_eq!(third.files[1], first.files[0]);
+
+        let files = fs::read_dir(manager.shapes_dir()).unwrap()
+            .filter(|entry| entry.as_ref().unwrap().path().extension().is_some_and(|ext| ext == "step"))
+            .count();
+        assert_eq!(files, 2);
+
+        let manifest = manager.read_model_manifest().unwrap();
+        assert_eq!(manifest.len(), 3);
+        assert_eq!(manifest["C25076"].files, vec![LockedItem::new(&format!("{}.step", first.name), &step[0].1)]);
+
+        // A changed file under the same name is written again
+        let path = manager.shapes_dir().join(&first.files[0].name);
+        fs::write(&path, b"stale").unwrap();
+        let updated = vec![(ModelFormat::Step, b"ISO-10303-21; 0402 rev 2".to_vec())];
+        manager.store_model("C25744", "R0402", b"uuid-0402", &updated).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), updated[0].1);
+
+        fs::remove_dir_all(&output).unwrap();
+    }
+}
@@ -1,5 +1,8 @@
 use clap::Parser;
 use nlbn::*;
+use nlbn::library::{sanitize_name, LockEntry, LockedItem, WrittenSymbol};
+use nlbn::lib_table::{self, Registration};
+use nlbn::model_path::{ModelFormat, ModelPathMode, ModelPaths};
 use std::process;
 use std::sync::{Arc, Mutex};
 use rayon::prelude::*;
@@ -11,13 +14,19 @@ fn main() {
         .init();
 
     // Parse CLI arguments
-    let args = Cli::parse();
+    let mut args = Cli::parse();
 
     // Set debug logging if requested
     if args.debug {
         log::set_max_level(log::LevelFilter::Debug);
     }
 
+    // Deprecated spelling of --model-path kiprjmod
+    if args.project_relative {
+        log::warn!("--project-relative is deprecated, use --model-path kiprjmod");
+        args.model_path = ModelPathMode::ProjectRelative;
+    }
+
     // Run the conversion
     if let Err(e) = run(args) {
         eprintln!("Error: {}", e);
@@ -29,6 +38,14 @@ fn run(args: Cli) -> error::Result<()> {
     // Validate arguments
     args.validate()?;
 
+    // Only register libraries converted earlier
+    if args.lcsc_id.is_none() && args.batch.is_none() {
+        return register_libraries(&args);
+    }
+
+    // Load conversion settings
+    let config = args.load_config()?;
+
     // Get list of LCSC IDs to process
     let lcsc_ids = args.get_lcsc_ids()?;
     let total_count = lcsc_ids.len();
@@ -41,9 +58,8 @@ fn run(args: Cli) -> error::Result<()> {
         }
     }
 
-    // Setup output directories
-    let lib_manager = LibraryManager::new(&args.output);
-    lib_manager.create_directories()?;
+    // Output library; parts may be routed to other libraries in the same directory
+    let lib_manager = LibraryManager::new(&args.output, &args.lib_name);
 
     // Initialize API
     let api = EasyedaApi::new();
@@ -64,7 +80,7 @@ fn run(args: Cli) -> error::Result<()> {
                     println!("\n[{}/{}] Processing: {}", index + 1, total_count, lcsc_id);
 
                     // Process single component
-                    match process_component(&args, &api, &lib_manager, lcsc_id) {
+                    match process_component(&args, &config, &api, &lib_manager, lcsc_id) {
                         Ok(_) => {
                             *success_count.lock().unwrap() += 1;
                             println!("✓ [{}/{}] Success: {}", index + 1, total_count, lcsc_id);
@@ -91,7 +107,7 @@ fn run(args: Cli) -> error::Result<()> {
             }
 
             // Process single component
-            match process_component(&args, &api, &lib_manager, lcsc_id) {
+            match process_component(&args, &config, &api, &lib_manager, lcsc_id) {
                 Ok(_) => {
                     *success_count.lock().unwrap() += 1;
                     if is_batch {
@@ -137,20 +153,37 @@ fn run(args: Cli) -> error::Result<()> {
         println!("Output directory: {}", args.output.display());
     }
 
+    if args.registers_libraries() {
+        register_libraries(&args)?;
+    }
+
     Ok(())
 }
 
-fn process_component(args: &Cli, api: &EasyedaApi, lib_manager: &LibraryManager, lcsc_id: &str) -> error::Result<()> {
+fn process_component(args: &Cli, config: &Config, api: &EasyedaApi, lib_manager: &LibraryManager, lcsc_id: &str) -> error::Result<()> {
     // Fetch component data from EasyEDA API
     let component_data = api.get_component_data(lcsc_id)?;
 
     log::info!("Fetched component: {}", component_data.title);
 
+    // KiCad reference prefix, used for library routing and the symbol
+    let reference = config.map_prefix(&SymbolImporter::reference_prefix(&component_data.prefix));
+
+    // Pick the library by reference prefix or LCSC category
+    let lib_name = config.library_for(&reference, &component_data.categories)
+        .unwrap_or(lib_manager.lib_name());
+    let lib_manager = lib_manager.with_library(lib_name);
+    lib_manager.create_directories()?;
+    log::info!("Library: {}", lib_manager.lib_name());
+
+    // Provenance of the part, recorded in the lock file once everything is written
+    let mut lock_entry = LockEntry::new(lib_manager.lib_name(), &component_data.revision, &component_data.raw_response, args.lock_options());
+
     // Process symbol (if requested)
     if args.symbol || args.full {
         log::info!("Converting symbol...");
 
-        let ee_symbol = SymbolImporter::parse(&component_data.data_str)?;
+        let ee_symbol = SymbolImporter::parse(&component_data.data_str, &component_data.prefix)?;
 
         // Use LCSC ID as unique identifier to prevent name collisions
         let component_name = format!("{}_{}", sanitize_name(&component_data.title), lcsc_id);
@@ -158,9 +191,9 @@ fn process_component(args: &Cli, api: &EasyedaApi, lib_manager: &LibraryManager,
 
         let mut ki_symbol = kicad::KiSymbol {
             name: component_name.clone(),
-            reference: ee_symbol.prefix.clone(),
+            reference: reference.clone(),
             value: component_data.title.clone(),
-            footprint: format!("nlbn:{}", footprint_name),
+            footprint: lib_manager.footprint_ref(&footprint_name),
             datasheet: component_data.datasheet.clone(),
             manufacturer: component_data.manufacturer.clone(),
             lcsc_id: component_data.lcsc_id.clone(),
@@ -170,8 +203,18 @@ fn process_component(args: &Cli, api: &EasyedaApi, lib_manager: &LibraryManager,
             circles: Vec::new(),
             arcs: Vec::new(),
             polylines: Vec::new(),
+            beziers: Vec::new(),
+            texts: Vec::new(),
+            pin_layout: kicad::PinTextLayout::default(),
         };
 
+        // Pin text visibility: hide names/numbers when EasyEDA hides them on every pin
+        if !ee_symbol.pins.is_empty() {
+            ki_symbol.pin_layout.names_hidden = ee_symbol.pins.iter().all(|p| !p.name_visible);
+            ki_symbol.pin_layout.numbers_hidden = ee_symbol.pins.iter().all(|p| !p.number_visible);
+        }
+        config.apply_pin_layout(&ki_symbol.reference, &mut ki_symbol.pin_layout);
+
         // Convert pins with bbox adjustment
         let _converter = Converter::new(args.kicad_version());
 
@@ -206,9 +249,21 @@ fn process_component(args: &Cli, api: &EasyedaApi, lib_manager: &LibraryManager,
                 pos_y: -adjusted_y,  // Back to negation to test
                 rotation: ee_pin.rotation,
                 length: ee_pin.length,
+                alternates: Vec::new(),
             });
         }
 
+        // Split multiplexed pin names into alternates (KiCad v6+ only)
+        if args.split_pin_names {
+            if args.kicad_version() == KicadVersion::V5 {
+                log::warn!("Alternate pin functions are not supported in KiCad v5 format, keeping full pin names");
+            } else {
+                for pin in &mut ki_symbol.pins {
+                    pin.split_alternates();
+                }
+            }
+        }
+
         // Convert rectangles with bbox adjustment
         for (idx, ee_rect) in ee_symbol.rectangles.iter().enumerate() {
             let adjusted_x = ee_rect.x - component_data.bbox_x;
@@ -314,7 +369,7 @@ fn process_component(args: &Cli, api: &EasyedaApi, lib_manager: &LibraryManager,
             ki_symbol.polylines.push(kicad::KiPolyline {
                 points: adjusted_points,
                 stroke_width: ee_polyline.stroke_width,
-                fill: false,
+                fill: kicad::FillType::None,
             });
         }
 
@@ -331,62 +386,42 @@ fn process_component(args: &Cli, api: &EasyedaApi, lib_manager: &LibraryManager,
             ki_symbol.polylines.push(kicad::KiPolyline {
                 points: adjusted_points,
                 stroke_width: ee_polygon.stroke_width,
-                fill: ee_polygon.fill,
+                fill: if ee_polygon.fill { kicad::FillType::Outline } else { kicad::FillType::None },
             });
         }
 
-        // Convert paths to polylines with bbox adjustment
-        // Parse SVG path commands (M, L, Z) and convert to polylines
+        // Convert paths to polylines and beziers with bbox adjustment
         for ee_path in &ee_symbol.paths {
-            let path_str = &ee_path.path_data;
-            let tokens: Vec<&str> = path_str.split_whitespace().collect();
-            let mut points = Vec::new();
-            let mut i = 0;
-
-            while i < tokens.len() {
-                let token = tokens[i];
-                match token {
-                    "M" | "L" => {
-                        // Move or Line command, followed by x,y coordinates
-                        if i + 1 < tokens.len() {
-                            i += 1;
-                            // Parse coordinate pair (may be "x,y" or separate "x" "y")
-                            let coord_str = tokens[i];
-                            if let Some((x_str, y_str)) = coord_str.split_once(',') {
-                                if let (Ok(x), Ok(y)) = (x_str.parse::<f64>(), y_str.parse::<f64>()) {
-                                    let adj_x = x - component_data.bbox_x;
-                                    let adj_y = component_data.bbox_y - y;
-                                    points.push((adj_x, adj_y));
-                                }
-                            } else if i + 1 < tokens.len() {
-                                // Separate x and y
-                                if let (Ok(x), Ok(y)) = (tokens[i].parse::<f64>(), tokens[i + 1].parse::<f64>()) {
-                                    let adj_x = x - component_data.bbox_x;
-                                    let adj_y = component_data.bbox_y - y;
-                                    points.push((adj_x, adj_y));
-                                    i += 1;
-                                }
-                            }
-                        }
-                    }
-                    "Z" | "z" => {
-                        // Close path: add line from current point back to start point
-                        if !points.is_empty() {
-                            let first_point = points[0];
-                            points.push(first_point);
-                        }
-                    }
-                    _ => {}
-                }
-                i += 1;
-            }
+            let fill = if ee_path.fill { kicad::FillType::Outline } else { kicad::FillType::None };
+            ki_symbol.push_path(
+                &ee_path.path_data,
+                (component_data.bbox_x, component_data.bbox_y),
+                ee_path.stroke_width,
+                fill,
+            );
+        }
 
-            if points.len() >= 2 {
-                ki_symbol.polylines.push(kicad::KiPolyline {
-                    points,
-                    stroke_width: ee_path.stroke_width,
-                    fill: ee_path.fill,
-                });
+        // Convert texts with bbox adjustment
+        for ee_text in &ee_symbol.texts {
+            ki_symbol.texts.push(kicad::SymbolKiText {
+                text: ee_text.text.clone(),
+                pos_x: ee_text.x - component_data.bbox_x,
+                pos_y: component_data.bbox_y - ee_text.y,  // bbox_y - pos_y
+                rotation: (360 - ee_text.rotation.rem_euclid(360)) % 360,
+                font_size: ee_text.font_size,
+            });
+        }
+
+        // Snap pins to the requested grid (1 px = 10 mil)
+        if let Some(grid_mil) = args.pin_grid {
+            let report = kicad::snap_to_grid(&mut ki_symbol, grid_mil / 10.0);
+            log::debug!("Pin grid shift: ({}, {}) px", report.shift.0, report.shift.1);
+            if !report.snapped.is_empty() {
+                log::info!("Snapped {} off-grid pin(s): {}", report.snapped.len(), report.snapped.join(", "));
+            }
+            if !report.unsnapped.is_empty() {
+                log::warn!("Could not snap {} pin(s) without overlap: {}",
+                    report.unsnapped.len(), report.unsnapped.join(", "));
             }
         }
 
@@ -394,12 +429,119 @@ fn process_component(args: &Cli, api: &EasyedaApi, lib_manager: &LibraryManager,
         let exporter = SymbolExporter::new(args.kicad_version());
         let symbol_data = exporter.export(&ki_symbol)?;
 
-        let lib_path = lib_manager.get_symbol_lib_path(args.v5);
+        let lib_path = lib_manager.get_symbol_lib_path(args.kicad_version() == KicadVersion::V5);
+
+        if args.derive_symbols && args.kicad_version() == KicadVersion::V5 {
+            log::warn!("Derived symbols are not supported in KiCad v5 format, writing full symbols");
+        }
 
         // Use thread-safe add_or_update method
-        lib_manager.add_or_update_component(&lib_path, &ki_symbol.name, &symbol_data, args.overwrite)?;
+        let written = if args.derive_symbols && args.kicad_version() != KicadVersion::V5 {
+            lib_manager.add_or_update_derived_component(
+                &lib_path, &ki_symbol.name, &symbol_data, args.overwrite,
+                |base_name| exporter.export_derived(&ki_symbol, base_name),
+            )?
+        } else {
+            lib_manager.add_or_update_component(&lib_path, &ki_symbol.name, &symbol_data, args.overwrite)?
+        };
+
+        match &written {
+            Some(WrittenSymbol { base: Some(base_name), .. }) => {
+                println!("✓ Symbol converted: {} (extends {})", ki_symbol.name, base_name)
+            }
+            Some(_) => println!("✓ Symbol converted: {}", ki_symbol.name),
+            None => println!("⚠ Symbol not written, kept the existing entry: {}", ki_symbol.name),
+        }
+        if let Some(written) = &written {
+            lock_entry.symbol = Some(LockedItem::new(&ki_symbol.name, written.data.as_bytes()));
+        }
+    }
+
+    // Process 3D model (if requested) before the footprint, which references the written files
+    let model_paths = ModelPaths::new(&lib_manager.shapes_dir(), args.model_path.clone());
+    let mut model_name = String::new();
+    let mut model_formats = Vec::new();
+    let mut step_info = None;
+    if args.model_3d || args.full {
+        if let Some(model_info) = &component_data.model_3d {
+            log::info!("Converting 3D model...");
+
+            let exporter = ModelExporter::new();
+            let mut model_files = Vec::new();
+
+            // VRML and glTF converted from the OBJ mesh keep the EasyEDA colors
+            let wants_obj = args.model_formats.contains(ModelFormat::Wrl) || args.model_formats.contains(ModelFormat::Glb);
+            let obj_data = if wants_obj {
+                api.download_3d_obj(&model_info.uuid)
+                    .inspect_err(|e| log::warn!("Failed to download OBJ model: {}", e))
+                    .ok()
+            } else {
+                None
+            };
+
+            if let Some(obj_data) = &obj_data
+                && args.model_formats.contains(ModelFormat::Wrl) {
+                match exporter.obj_to_wrl(obj_data) {
+                    Ok(wrl_data) => model_files.push((ModelFormat::Wrl, wrl_data.into_bytes())),
+                    Err(e) => log::warn!("Failed to convert OBJ model: {}", e),
+                }
+            }
+
+            if let Some(obj_data) = &obj_data
+                && args.model_formats.contains(ModelFormat::Glb) {
+                match exporter.obj_to_gltf(obj_data) {
+                    Ok(glb_data) => model_files.push((ModelFormat::Glb, glb_data)),
+                    Err(e) => log::warn!("Failed to convert OBJ model to glTF: {}", e),
+                }
+            }
+
+            if args.model_formats.contains(ModelFormat::Step) {
+                match api.download_3d_step(&model_info.uuid) {
+                    Ok(step_data) => {
+                        // Rejects HTML error pages and truncated downloads
+                        match exporter.inspect_step(&step_data) {
+                            Ok(info) => {
+                                let size = info.size();
+                                log::info!(
+                                    "STEP model {}: {:.2} x {:.2} x {:.2} mm ({} units, {} entities)",
+                                    info.product_name.as_deref().unwrap_or("(unnamed)"),
+                                    size.0, size.1, size.2,
+                                    info.unit,
+                                    info.entity_count
+                                );
+                                model_files.push((ModelFormat::Step, step_data));
+                                step_info = Some(info);
+                            }
+                            Err(e) => log::warn!("Skipping STEP model: {}", e),
+                        }
+                    }
+                    Err(e) => log::warn!("Failed to download STEP model: {}", e),
+                }
+            }
 
-        println!("✓ Symbol converted: {}", ki_symbol.name);
+            // Parts with identical models share one set of files named by package
+            let package = if component_data.package.is_empty() { &model_info.title } else { &component_data.package };
+            if model_files.is_empty() {
+                println!("⚠ 3D model not available");
+            } else {
+                // Identical STEP files uploaded under different uuids share one set of files
+                let source = model_files.iter()
+                    .find(|(format, _)| *format == ModelFormat::Step)
+                    .map_or(model_info.uuid.as_bytes(), |(_, data)| data.as_slice());
+                match lib_manager.store_model(lcsc_id, package, source, &model_files) {
+                    Ok(entry) => {
+                        model_formats = model_files.iter().map(|(format, _)| *format).collect();
+                        let file_names: Vec<&str> = entry.files.iter().map(|file| file.name.as_str()).collect();
+                        println!("✓ 3D model converted: {} ({})", entry.name, file_names.join(", "));
+                        model_name = entry.name.clone();
+                        lock_entry.model = Some(entry);
+                    }
+                    Err(e) => log::warn!("Failed to write 3D model: {}", e),
+                }
+            }
+        } else {
+            log::warn!("No 3D model metadata available for this component");
+        }
     }
 
     // Process footprint (if requested)
@@ -407,7 +549,7 @@ fn process_component(args: &Cli, api: &EasyedaApi, lib_manager: &LibraryManager,
         log::info!("Converting footprint...");
 
         let ee_footprint = FootprintImporter::parse(&component_data.package_detail)?;
-        let _converter = Converter::new(args.kicad_version());
+        let converter = Converter::new(args.kicad_version());
 
         // Use LCSC ID as unique identifier to prevent name collisions
         let footprint_name = format!("{}_{}", sanitize_name(&component_data.title), lcsc_id);
@@ -415,6 +557,11 @@ fn process_component(args: &Cli, api: &EasyedaApi, lib_manager: &LibraryManager,
         // Convert EasyEDA footprint to KiCad footprint
         let mut ki_footprint = kicad::KiFootprint {
             name: footprint_name,
+            lcsc_id: lcsc_id.to_string(),
+            datasheet: component_data.datasheet.clone(),
+            description: footprint_description(&component_data),
+            tags: footprint_tags(&component_data),
+            properties: vec![("LCSC".to_string(), lcsc_id.to_string())],
             pads: Vec::new(),
             tracks: Vec::new(),
             circles: Vec::new(),
@@ -422,6 +569,7 @@ fn process_component(args: &Cli, api: &EasyedaApi, lib_manager: &LibraryManager,
             texts: Vec::new(),
             lines: Vec::new(),
             model_3d: None,
+            settings: config.footprint_defaults.pad_settings(),
         };
 
         // Convert pads with bbox adjustment
@@ -481,50 +629,82 @@ fn process_component(args: &Cli, api: &EasyedaApi, lib_manager: &LibraryManager,
             let adjusted_x = ee_pad.x - component_data.package_bbox_x;
             let adjusted_y = ee_pad.y - component_data.package_bbox_y;
 
-            // Handle polygon pads
-            let (size_x, size_y, rotation, polygon) = if ee_pad.shape == "POLYGON" && !ee_pad.points.is_empty() {
-                // Parse points: space-separated x y coordinates
+            // Polygon outline in footprint coordinates (space-separated x y pairs)
+            let outline: Vec<(f64, f64)> = if ee_pad.shape == "POLYGON" {
                 let coords: Vec<f64> = ee_pad.points
                     .split_whitespace()
                     .filter_map(|s| s.parse::<f64>().ok())
                     .collect();
-
-                if coords.len() >= 4 {  // At least 2 points (x,y pairs)
-                    // Generate polygon with coordinates relative to pad position
-                    let mut poly_str = String::from("\n\t\t(primitives \n\t\t\t(gr_poly \n\t\t\t\t(pts");
-
-                    for i in (0..coords.len()).step_by(2) {
-                        if i + 1 < coords.len() {
-                            let rel_x = coords[i] - component_data.package_bbox_x - adjusted_x;
-                            let rel_y = coords[i + 1] - component_data.package_bbox_y - adjusted_y;
-                            poly_str.push_str(&format!(" (xy {:.2} {:.2})", rel_x, rel_y));
-                        }
-                    }
-
-                    poly_str.push_str("\n\t\t\t\t) \n\t\t\t\t(width 0.1) \n\t\t\t)\n\t\t)\n\t");
-
-                    // Set minimal pad size and force orientation to 0
-                    (0.005, 0.005, 0.0, Some(poly_str))
-                } else {
-                    (ee_pad.width, ee_pad.height, ee_pad.rotation, None)
-                }
+                coords.chunks_exact(2)
+                    .map(|xy| (xy[0] - component_data.package_bbox_x, xy[1] - component_data.package_bbox_y))
+                    .collect()
             } else {
-                (ee_pad.width, ee_pad.height, ee_pad.rotation, None)
+                Vec::new()
             };
 
-            ki_footprint.pads.push(kicad::KiPad {
+            // Polygon pads that are plain, rounded or chamfered rectangles become native pads
+            if drill.is_none()
+                && let Some(detected) = kicad::detect_polygon_pad(&outline)
+            {
+                ki_footprint.pads.push(kicad::KiPad {
+                    number: ee_pad.number.clone(),
+                    pad_type,
+                    shape: detected.shape,
+                    pos_x: detected.center.0,
+                    pos_y: detected.center.1,
+                    size_x: detected.size.0,
+                    size_y: detected.size.1,
+                    rotation: 0.0,
+                    layers,
+                    drill,
+                    custom: None,
+                    roundrect_ratio: detected.roundrect_ratio,
+                    chamfer: detected.chamfer,
+                    settings: kicad::PadSettings::default(),
+                });
+                continue;
+            }
+
+            let mut ki_pad = kicad::KiPad {
                 number: ee_pad.number.clone(),
                 pad_type,
                 shape: kicad::PadShape::from_easyeda(&ee_pad.shape),
                 pos_x: adjusted_x,
                 pos_y: adjusted_y,
-                size_x,
-                size_y,
-                rotation,
+                size_x: ee_pad.width,
+                size_y: ee_pad.height,
+                rotation: ee_pad.rotation,
                 layers,
                 drill,
-                polygon,
-            });
+                custom: None,
+                roundrect_ratio: 0.0,
+                chamfer: None,
+                settings: kicad::PadSettings::default(),
+            };
+
+            // Other polygon pads become custom pads with an anchor inside the polygon
+            if ee_pad.shape == "POLYGON" {
+                let fixed_origin = ki_pad.drill.is_some();
+                match kicad::build_custom_pad((adjusted_x, adjusted_y), ee_pad.rotation, &outline, fixed_origin) {
+                    Some(layout) => {
+                        ki_pad.pos_x = layout.position.0;
+                        ki_pad.pos_y = layout.position.1;
+                        ki_pad.size_x = layout.anchor_size;
+                        ki_pad.size_y = layout.anchor_size;
+                        ki_pad.custom = Some(layout.custom);
+
+                        if !kicad::anchor_fits(&ki_pad) {
+                            log::warn!("Pad {}: anchor does not fit inside the polygon", ki_pad.number);
+                        }
+                    }
+                    None => {
+                        log::warn!("Pad {}: invalid polygon outline, using a rectangular pad", ee_pad.number);
+                        ki_pad.shape = kicad::PadShape::Rect;
+                    }
+                }
+            }
+
+            ki_footprint.pads.push(ki_pad);
         }
 
         // Convert tracks to lines with bbox adjustment
@@ -603,7 +783,10 @@ fn process_component(args: &Cli, api: &EasyedaApi, lib_manager: &LibraryManager,
                     offset_x: 0.0,
                     offset_y: 0.0,
                 }),
-                polygon: None,
+                custom: None,
+                roundrect_ratio: 0.0,
+                chamfer: None,
+                settings: kicad::PadSettings::default(),
             });
         }
 
@@ -632,7 +815,10 @@ fn process_component(args: &Cli, api: &EasyedaApi, lib_manager: &LibraryManager,
                     offset_x: 0.0,
                     offset_y: 0.0,
                 }),
-                polygon: None,
+                custom: None,
+                roundrect_ratio: 0.0,
+                chamfer: None,
+                settings: kicad::PadSettings::default(),
             });
         }
 
@@ -741,76 +927,210 @@ fn process_component(args: &Cli, api: &EasyedaApi, lib_manager: &LibraryManager,
             });
         }
 
-        // Add 3D model reference if available
-        if let Some(model_info) = &component_data.model_3d {
-            if args.model_3d || args.full {
-                // Default to project-relative paths (KIPRJMOD) for easier setup
-                // Use --project-relative flag to force global paths if needed
-                let model_path = if args.project_relative {
-                    format!("${{E2K/e2k.3dshapes/{}.wrl", sanitize_name(&model_info.title))
-                } else {
-                    format!("${{E2K}}/e2k.3dshapes/{}.wrl", sanitize_name(&model_info.title))
-                };
-
-                ki_footprint.model_3d = Some(kicad::Ki3dModel {
-                    path: model_path,
-                    offset: (0.0, 0.0, 0.0),
-                    scale: (1.0, 1.0, 1.0),
-                    rotate: (0.0, 0.0, 0.0),
-                });
+        // Round rectangular SMD pad corners
+        kicad::apply_roundrect_policy(&mut ki_footprint, args.roundrect);
+
+        // Split the paste of exposed pads into a grid of apertures
+        if let Some(grid) = &config.footprint_defaults.paste_grid {
+            let windowed = kicad::window_exposed_pads(&mut ki_footprint, grid);
+            if windowed > 0 {
+                log::info!("Split paste of {} exposed pad(s) into apertures", windowed);
+            }
+        }
+
+        // Generate fab outline and courtyard from pads and body extents
+        kicad::add_fab_outline(&mut ki_footprint);
+        kicad::add_courtyard(&mut ki_footprint, args.courtyard_clearance);
+
+        // Keep silkscreen off pads and mask openings
+        if let Some(clearance) = args.silk_clearance {
+            let silk_report = kicad::clip_silkscreen(&mut ki_footprint, clearance, &converter);
+            if silk_report.clipped_items > 0 {
+                log::info!(
+                    "Clipped {:.2} mm of silkscreen from {} item(s) near pads",
+                    silk_report.removed_length,
+                    silk_report.clipped_items
+                );
+            }
+        }
+
+        // Reference the model in whichever format was written
+        if let Some(format) = ModelPaths::preferred_format(&model_formats)
+            && let Some(model_info) = &component_data.model_3d {
+            // Model origin relative to the footprint origin; KiCad's 3D y axis points up.
+            // SMD bodies are lowered by the EasyEDA z offset, through-hole bodies sit on the board
+            let offset_z = match ki_footprint.attribute() {
+                Some(kicad::FootprintAttribute::ThroughHole) => 0.0,
+                _ => -converter.px_to_mm(model_info.z),
+            };
+            // EasyEDA rotates clockwise, KiCad counter-clockwise
+            let rotate = |angle: f64| (360.0 - angle).rem_euclid(360.0);
+
+            ki_footprint.model_3d = Some(kicad::Ki3dModel {
+                path: model_paths.reference(&model_name, format),
+                offset: (
+                    converter.px_to_mm(model_info.origin.0 - component_data.package_bbox_x),
+                    -converter.px_to_mm(model_info.origin.1 - component_data.package_bbox_y),
+                    offset_z,
+                ),
+                scale: (1.0, 1.0, 1.0),
+                rotate: (
+                    rotate(model_info.rotation.0),
+                    rotate(model_info.rotation.1),
+                    rotate(model_info.rotation.2),
+                ),
+            });
+        }
+
+        // Synthesize a plain body for parts without a usable model
+        if args.fallback_3d && ki_footprint.model_3d.is_none() {
+            let height = component_data.body_height
+                .unwrap_or_else(|| config.body_height(&component_data.package));
+
+            if let Some(body) = kicad::ExtrudedBody::from_footprint(&ki_footprint, height, args.courtyard_clearance) {
+                let package = if component_data.package.is_empty() { &ki_footprint.name } else { &component_data.package };
+                let body_name = format!("{}_body", package);
+                let step = body.to_step(&body_name).into_bytes();
+                let body_files = vec![
+                    (ModelFormat::Wrl, body.to_wrl().into_bytes()),
+                    (ModelFormat::Step, step.clone()),
+                ];
+
+                match lib_manager.store_model(lcsc_id, &body_name, &step, &body_files) {
+                    Ok(entry) => {
+                        // The body is drawn in footprint coordinates, so no offset or rotation
+                        ki_footprint.model_3d = Some(kicad::Ki3dModel {
+                            path: model_paths.reference(&entry.name, ModelFormat::Wrl),
+                            offset: (0.0, 0.0, 0.0),
+                            scale: (1.0, 1.0, 1.0),
+                            rotate: (0.0, 0.0, 0.0),
+                        });
+                        println!("✓ Fallback 3D body generated: {} ({:.2} mm high)", entry.name, height);
+                        lock_entry.model = Some(entry);
+                    }
+                    Err(e) => log::warn!("Failed to write fallback 3D body: {}", e),
+                }
+            } else {
+                log::warn!("No fab or courtyard outline to build a fallback 3D body from");
+            }
+        }
+
+        // Compare the STEP body with the footprint it is attached to
+        if let (Some(info), Some(model)) = (&step_info, &ki_footprint.model_3d) {
+            for warning in kicad::check_model_placement(info, model, &ki_footprint) {
+                log::warn!("{}: {}", ki_footprint.name, warning);
             }
         }
 
         // Export footprint
-        let exporter = FootprintExporter::new();
+        let exporter = FootprintExporter::new(args.kicad_version()).with_text_layout(args.text_layout);
         let footprint_data = exporter.export(&ki_footprint)?;
         lib_manager.write_footprint(&ki_footprint.name, &footprint_data)?;
+        lock_entry.footprint = Some(LockedItem::new(
+            &lib_manager.footprint_file(&ki_footprint.name),
+            footprint_data.as_bytes(),
+        ));
 
         println!("✓ Footprint converted: {}", ki_footprint.name);
     }
 
-    // Process 3D model (if requested)
-    if args.model_3d || args.full {
-        if let Some(model_info) = &component_data.model_3d {
-            log::info!("Converting 3D model...");
+    lib_manager.record_component(lcsc_id, lock_entry)?;
 
-            // Only download STEP format (skip OBJ/WRL conversion)
-            match api.download_3d_step(&model_info.uuid) {
-                Ok(step_data) => {
-                    let exporter = ModelExporter::new();
-                    match exporter.export_step(&step_data) {
-                        Ok(step_data) => {
-                            // Use LCSC ID as unique identifier to prevent name collisions
-                            let model_name = format!("{}_{}", sanitize_name(&model_info.title), lcsc_id);
-                            match lib_manager.write_step_model(&model_name, &step_data) {
-                                Ok(_) => println!("✓ 3D model converted: {} (STEP)", model_name),
-                                Err(e) => log::warn!("Failed to write STEP model: {}", e),
-                            }
-                        }
-                        Err(e) => log::warn!("Failed to export STEP model: {}", e),
-                    }
-                }
-                Err(e) => {
-                    log::warn!("Failed to download STEP model: {}", e);
-                    println!("⚠ 3D model not available");
-                }
+    Ok(())
+}
+
+/// Add the libraries in the output directory to a project's or KiCad's global library tables
+fn register_libraries(args: &Cli) -> error::Result<()> {
+    let (table_dir, project_dir) = match (&args.register_project, &args.register_global) {
+        (Some(dir), _) => (dir, Some(dir.as_path())),
+        (None, Some(dir)) => (dir, None),
+        (None, None) => return Ok(()),
+    };
+
+    // Only the libraries nlbn writes: --lib-name and the configured routing rules
+    let config = args.load_config()?;
+    let mut names = vec![args.lib_name.as_str()];
+    names.extend(config.libraries.iter().map(|rule| rule.name.as_str()));
+    let libraries = lib_table::discover_libraries(&args.output, &names)?;
+    if libraries.is_empty() {
+        println!("⚠ No libraries to register in {}", args.output.display());
+        return Ok(());
+    }
+
+    // KiCad 6 rejects the table version token and shares the V6 format with KiCad 7,
+    // so the token is only written for KiCad 8 and 9
+    let versioned = matches!(args.kicad_version(), KicadVersion::V8 | KicadVersion::V9);
+
+    for kind in [lib_table::LibTableKind::Symbol, lib_table::LibTableKind::Footprint] {
+        let entries: Vec<_> = libraries.iter()
+            .filter(|library| library.kind == kind)
+            .map(|library| lib_table::LibTableEntry::new(
+                &library.name,
+                library.lib_type,
+                &lib_table::library_uri(&library.path, &args.model_path, project_dir),
+            ))
+            .collect();
+        if entries.is_empty() {
+            continue;
+        }
+
+        let results = lib_table::update_lib_table(table_dir, kind, &entries, args.overwrite, versioned)?;
+        for (name, registration) in results {
+            match registration {
+                Registration::Added => println!("✓ Registered {} in {}", name, kind.file_name()),
+                Registration::Updated => println!("✓ Updated {} in {}", name, kind.file_name()),
+                Registration::Unchanged => log::info!("{} is already registered in {}", name, kind.file_name()),
+                Registration::Kept(uri) => println!(
+                    "⚠ {} already points to {} in {} (use --overwrite to replace)",
+                    name, uri, kind.file_name()
+                ),
             }
-        } else {
-            log::warn!("No 3D model metadata available for this component");
+        }
+    }
+
+    // Libraries and models referenced through a path variable need it defined
+    if let ModelPathMode::EnvVar(var) = &args.model_path {
+        match &args.register_global {
+            Some(config_dir) => match lib_table::set_path_variable(config_dir, var, &args.output, args.overwrite)? {
+                Registration::Added | Registration::Updated => println!("✓ Defined path variable {}", var),
+                Registration::Unchanged => {}
+                Registration::Kept(value) => println!(
+                    "⚠ Path variable {} already points to {} (use --overwrite to replace)",
+                    var, value
+                ),
+            },
+            None => println!(
+                "⚠ Define {} = {} under Preferences > Configure Paths",
+                var,
+                std::path::absolute(&args.output).unwrap_or_else(|_| args.output.clone()).display()
+            ),
         }
     }
 
     Ok(())
 }
 
-fn sanitize_name(name: &str) -> String {
-    name.chars()
-        .map(|c| {
-            if c.is_alphanumeric() || c == '_' || c == '-' {
-                c
-            } else {
-                '_'
-            }
-        })
-        .collect()
+/// Footprint description from the part title, package, LCSC ID and datasheet
+fn footprint_description(data: &easyeda::ComponentData) -> String {
+    let mut parts = vec![data.title.clone()];
+    if !data.package.is_empty() {
+        parts.push(format!("{} package", data.package));
+    }
+    parts.push(format!("LCSC part {}", data.lcsc_id));
+    if !data.datasheet.is_empty() {
+        parts.push(data.datasheet.clone());
+    }
+    parts.join(", ")
+}
+
+/// Search keywords for the footprint browser: package name, title and LCSC ID
+fn footprint_tags(data: &easyeda::ComponentData) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for keyword in [&data.package, &data.title, &data.lcsc_id] {
+        let keyword = keyword.split_whitespace().collect::<Vec<_>>().join("_");
+        if !keyword.is_empty() && !tags.contains(&keyword) {
+            tags.push(keyword);
+        }
+    }
+    tags
 }
@@ -0,0 +1,221 @@
+use crate::library::sanitize_name;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+/// Path variable used by `--model-path env` when no name is given
+pub const DEFAULT_MODEL_ENV_VAR: &str = "NLBN_LIB_DIR";
+
+/// How footprints refer to their 3D model files
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ModelPathMode {
+    /// `${KIPRJMOD}/<output>/<lib>.3dshapes/...`, for libraries kept inside the project
+    #[default]
+    ProjectRelative,
+    /// `${VAR}/<lib>.3dshapes/...`, where VAR points to the output directory
+    EnvVar(String),
+    /// Absolute path of the written file
+    Absolute,
+}
+
+impl FromStr for ModelPathMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "kiprjmod" => Ok(ModelPathMode::ProjectRelative),
+            "absolute" => Ok(ModelPathMode::Absolute),
+            "env" => Ok(ModelPathMode::EnvVar(DEFAULT_MODEL_ENV_VAR.to_string())),
+            _ => match s.split_once(':') {
+                Some((mode, var)) if mode.eq_ignore_ascii_case("env") && !var.is_empty() => {
+                    Ok(ModelPathMode::EnvVar(var.to_string()))
+                }
+                _ => Err(format!("expected 'kiprjmod', 'absolute', 'env' or 'env:<VAR>', got '{}'", s)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for ModelPathMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelPathMode::ProjectRelative => write!(f, "kiprjmod"),
+            ModelPathMode::EnvVar(var) => write!(f, "env:{}", var),
+            ModelPathMode::Absolute => write!(f, "absolute"),
+        }
+    }
+}
+
+/// 3D model file formats written next to the footprints
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelFormat {
+    Wrl,
+    Step,
+    /// Binary glTF for web and mechanical viewers; not referenced by footprints
+    Glb,
+}
+
+impl ModelFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ModelFormat::Wrl => "wrl",
+            ModelFormat::Step => "step",
+            ModelFormat::Glb => "glb",
+        }
+    }
+}
+
+/// Which model formats to write (--3d-format), e.g. "step" or "wrl,step,glb"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelFormats(Vec<ModelFormat>);
+
+impl ModelFormats {
+    pub fn contains(&self, format: ModelFormat) -> bool {
+        self.0.contains(&format)
+    }
+}
+
+impl Default for ModelFormats {
+    fn default() -> Self {
+        Self(vec![ModelFormat::Step])
+    }
+}
+
+impl fmt::Display for ModelFormats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let extensions: Vec<&str> = self.0.iter().map(|format| format.extension()).collect();
+        write!(f, "{}", extensions.join(","))
+    }
+}
+
+impl FromStr for ModelFormats {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut formats = Vec::new();
+        for part in s.split(',') {
+            let added: &[ModelFormat] = match part.trim().to_ascii_lowercase().as_str() {
+                "wrl" | "vrml" => &[ModelFormat::Wrl],
+                "step" | "stp" => &[ModelFormat::Step],
+                "glb" | "gltf" => &[ModelFormat::Glb],
+                "both" => &[ModelFormat::Wrl, ModelFormat::Step],
+                _ => return Err(format!("expected 'wrl', 'step', 'glb' or 'both' (comma separated), got '{}'", s)),
+            };
+            for format in added {
+                if !formats.contains(format) {
+                    formats.push(*format);
+                }
+            }
+        }
+        Ok(Self(formats))
+    }
+}
+
+/// Naming and path resolution of 3D models, shared by the model and footprint stages
+#[derive(Debug, Clone)]
+pub struct ModelPaths {
+    shapes_dir: PathBuf,
+    mode: ModelPathMode,
+}
+
+impl ModelPaths {
+    /// `shapes_dir` is the model directory as the user gave it (possibly relative)
+    pub fn new(shapes_dir: &Path, mode: ModelPathMode) -> Self {
+        Self {
+            shapes_dir: shapes_dir.to_path_buf(),
+            mode,
+        }
+    }
+
+    /// File stem of a shared model: package name and a short content hash
+    pub fn model_name(package: &str, hash: &str) -> String {
+        format!("{}_{}", sanitize_name(package), &hash[..hash.len().min(8)])
+    }
+
+    /// Where the model file is written
+    pub fn file_path(&self, name: &str, format: ModelFormat) -> PathBuf {
+        self.shapes_dir.join(format!("{}.{}", name, format.extension()))
+    }
+
+    /// Path written into the footprint's `(model ...)` entry
+    pub fn reference(&self, name: &str, format: ModelFormat) -> String {
+        let file_name = format!("{}.{}", name, format.extension());
+
+        match &self.mode {
+            ModelPathMode::ProjectRelative if self.shapes_dir.is_relative() => {
+                let mut reference = String::from("${KIPRJMOD}");
+                for component in self.shapes_dir.components() {
+                    if let Component::Normal(part) = component {
+                        reference.push('/');
+                        reference.push_str(&part.to_string_lossy());
+                    } else if component == Component::ParentDir {
+                        reference.push_str("/..");
+                    }
+                }
+                format!("{}/{}", reference, file_name)
+            }
+            ModelPathMode::EnvVar(var) => {
+                let dir_name = self.shapes_dir.file_name().unwrap_or_default().to_string_lossy();
+                format!("${{{}}}/{}/{}", var, dir_name, file_name)
+            }
+            // An absolute output directory cannot be expressed relative to the project
+            ModelPathMode::ProjectRelative | ModelPathMode::Absolute => {
+                let path = std::path::absolute(self.file_path(name, format))
+                    .unwrap_or_else(|_| self.file_path(name, format));
+                path.to_string_lossy().replace('\\', "/")
+            }
+        }
+    }
+
+    /// Format the footprint should reference among those written: KiCad renders
+    /// WRL and substitutes the STEP file of the same name on export
+    pub fn preferred_format(written: &[ModelFormat]) -> Option<ModelFormat> {
+        [ModelFormat::Wrl, ModelFormat::Step]
+            .into_iter()
+            .find(|format| written.contains(format))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_parsing() {
+        assert_eq!("kiprjmod".parse::<ModelPathMode>(), Ok(ModelPathMode::ProjectRelative));
+        assert_eq!("env".parse::<ModelPathMode>(), Ok(ModelPathMode::EnvVar(DEFAULT_MODEL_ENV_VAR.to_string())));
+        assert_eq!("env:MY_LIBS".parse::<ModelPathMode>(), Ok(ModelPathMode::EnvVar("MY_LIBS".to_string())));
+        assert!("env:".parse::<ModelPathMode>().is_err());
+        assert!("global".parse::<ModelPathMode>().is_err());
+
+        let formats = "both,glb".parse::<ModelFormats>().unwrap();
+        assert!(formats.contains(ModelFormat::Wrl) && formats.contains(ModelFormat::Step) && formats.contains(ModelFormat::Glb));
+        assert!(!ModelFormats::default().contains(ModelFormat::Wrl));
+        assert!("obj".parse::<ModelFormats>().is_err());
+        assert_eq!(formats.to_string(), "wrl,step,glb");
+        assert_eq!(ModelPathMode::EnvVar("MY_LIBS".to_string()).to_string(), "env:MY_LIBS");
+    }
+
+    #[test]
+    fn test_reference_matches_written_file() {
+        let shapes_dir = Path::new("./libs").join("nlbn.3dshapes");
+        let name = ModelPaths::model_name("SOT-23 (3)", "c20917a0b1c2d3e4");
+        assert_eq!(name, "SOT-23__3__c20917a0");
+
+        let paths = ModelPaths::new(&shapes_dir, ModelPathMode::ProjectRelative);
+        assert_eq!(paths.file_path(&name, ModelFormat::Step), shapes_dir.join("SOT-23__3__c20917a0.step"));
+        assert_eq!(
+            paths.reference(&name, ModelFormat::Step),
+            "${KIPRJMOD}/libs/nlbn.3dshapes/SOT-23__3__c20917a0.step"
+        );
+
+        let paths = ModelPaths::new(&shapes_dir, ModelPathMode::EnvVar("NLBN_LIB_DIR".to_string()));
+        assert_eq!(paths.reference(&name, ModelFormat::Wrl), "${NLBN_LIB_DIR}/nlbn.3dshapes/SOT-23__3__c20917a0.wrl");
+
+        let paths = ModelPaths::new(&shapes_dir, ModelPathMode::Absolute);
+        assert!(Path::new(&paths.reference(&name, ModelFormat::Wrl)).is_absolute());
+
+        assert_eq!(ModelPaths::preferred_format(&[ModelFormat::Step, ModelFormat::Wrl]), Some(ModelFormat::Wrl));
+        assert_eq!(ModelPaths::preferred_format(&[]), None);
+    }
+}